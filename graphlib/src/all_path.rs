@@ -1,71 +1,357 @@
-use super::{EdgeTypeTrait, Graph, GraphEdgeTrait, GraphVertexTrait};
-use std::collections::BTreeMap;
+use super::single_path::{dijkstra_unchecked, relax_rounds};
+use super::{
+    GraphEdgeTrait, GraphError, GraphPayloadTrait, GraphStorage, GraphVertexTrait, NegativeCycle,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Sub;
 
-/// All-Pairs Shortest Path algorithms.
+// All-Pairs Shortest Path algorithms.
+
+/// Result of an all-pairs shortest path search.
+/// Holds the |V|x|V| distance matrix (`E::max_value()` when there is no path)
+/// and the next-hop matrix used to rebuild the path between any two vertices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllPairsShortestPaths<V: GraphVertexTrait, E: GraphEdgeTrait> {
+    distances: BTreeMap<V, BTreeMap<V, E>>,
+    next_hops: BTreeMap<V, BTreeMap<V, V>>,
+}
+
+impl<V, E> AllPairsShortestPaths<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+    pub fn distances(&self) -> &BTreeMap<V, BTreeMap<V, E>> {
+        &self.distances
+    }
+
+    pub fn into_distances(self) -> BTreeMap<V, BTreeMap<V, E>> {
+        self.distances
+    }
+
+    pub fn distance(&self, source: V, destination: V) -> Option<E> {
+        self.distances.get(&source)?.get(&destination).copied()
+    }
+
+    /// The vertex that follows source on the shortest path to destination
+    pub fn next_hop(&self, source: V, destination: V) -> Option<V> {
+        self.next_hops.get(&source)?.get(&destination).cloned()
+    }
+
+    /// Follows the next-hop matrix from source to destination.
+    /// Returns None when there is no path between them.
+    pub fn path(&self, source: V, destination: V) -> Option<Vec<V>> {
+        if !self.distances.contains_key(&source) || !self.distances.contains_key(&destination) {
+            return None;
+        }
+        let mut path = vec![source.clone()];
+        let mut current = source;
+        while current != destination {
+            current = self.next_hops.get(&current)?.get(&destination)?.clone();
+            if path.len() > self.distances.len() {
+                return None;
+            }
+            path.push(current.clone());
+        }
+        Some(path)
+    }
+}
 
 /// Floyd-Warshall algorithm
-pub fn floyd_warshall<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
-    graph: &Graph<V, E, T>,
-) -> Option<BTreeMap<V, BTreeMap<V, E>>> {
-    let mut weight_matrix: BTreeMap<V, BTreeMap<V, E>> = BTreeMap::new(); // |V|x|V| matrix
+/// Stops as soon as a vertex gets a negative distance to itself, which means it sits on a
+/// negative cycle, and fails with the cycle rebuilt from the next-hop matrix
+pub fn floyd_warshall<V, E, G>(graph: &G) -> Result<AllPairsShortestPaths<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    floyd_warshall_with(graph, |_, _, value| *value)
+}
+
+/// Floyd-Warshall over the weights the closure takes from the edge payloads
+pub fn floyd_warshall_by<V, E, W, G, F>(
+    graph: &G,
+    weight: F,
+) -> Result<AllPairsShortestPaths<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&E) -> W,
+{
+    floyd_warshall_with(graph, |_, _, value| weight(value))
+}
+
+/// Floyd-Warshall with the weight of each edge computed by the closure from (from, to, value)
+pub fn floyd_warshall_with<V, E, W, G, F>(
+    graph: &G,
+    weight: F,
+) -> Result<AllPairsShortestPaths<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    let mut weight_matrix: BTreeMap<V, BTreeMap<V, W>> = BTreeMap::new(); // |V|x|V| matrix
+    let mut next_hops: BTreeMap<V, BTreeMap<V, V>> = BTreeMap::new();
 
     // Build a weight matrix from input graph
-    for u in graph.vertices() {
-        weight_matrix.insert(*u, BTreeMap::new());
-        for v in graph.vertices() {
+    for u in graph.iter_vertices() {
+        let mut row = BTreeMap::new();
+        for v in graph.iter_vertices() {
             if u == v {
-                weight_matrix
-                    .entry(*u)
-                    .or_default()
-                    .insert(*v, E::default());
+                row.insert(v.clone(), W::default());
             } else {
-                weight_matrix
-                    .entry(*u)
-                    .or_default()
-                    .insert(*v, E::max_value());
+                row.insert(v.clone(), W::max_value());
             }
         }
+        weight_matrix.insert(u.clone(), row);
+        next_hops.insert(u.clone(), BTreeMap::from([(u.clone(), u.clone())]));
     }
-    // update distances already known
-    for (u, v, weight) in graph.edges() {
-        weight_matrix.entry(u).or_default().insert(v, weight);
+    // update distances already known, keeping the lightest of parallel edges
+    for (u, v, value) in graph.iter_edges() {
+        let value = weight(u, v, value);
+        if value < weight_matrix[u][v] {
+            weight_matrix.get_mut(u).unwrap().insert(v.clone(), value);
+            next_hops.get_mut(u).unwrap().insert(v.clone(), v.clone());
+        }
     }
 
-    let keys = weight_matrix.keys().copied().collect::<Vec<_>>();
+    let keys = weight_matrix.keys().cloned().collect::<Vec<_>>();
+    // A negative loop is a cycle on its own
+    for u in &keys {
+        if weight_matrix[u][u] < W::default() {
+            return Err(NegativeCycle::new(vec![u.clone()], weight_matrix[u][u]).into());
+        }
+    }
+    let edges = weight_matrix.clone();
 
-    for &intermediate in &keys {
-        for &source in &keys {
-            for &destination in &keys {
-                let ij = weight_matrix[&source][&destination];
-                let ik = weight_matrix[&source][&intermediate];
-                let kj = weight_matrix[&intermediate][&destination];
+    for intermediate in &keys {
+        for source in &keys {
+            let ik = weight_matrix[source][intermediate];
+            if ik == W::max_value() {
+                continue;
+            }
+            for destination in &keys {
+                let ij = weight_matrix[source][destination];
+                let kj = weight_matrix[intermediate][destination];
+                if kj == W::max_value() {
+                    continue;
+                }
                 let result = ik.checked_add(&kj);
                 if let Some(sum) = result {
+                    if source == destination && sum < W::default() {
+                        // Nothing was negative before this update, so the hops
+                        // still lead through intermediate and back to source
+                        return Err(negative_cycle(&edges, &next_hops, source, intermediate).into());
+                    }
                     if ij > sum {
-                        weight_matrix.entry(source).or_default().insert(destination, sum);
+                        weight_matrix
+                            .get_mut(source)
+                            .unwrap()
+                            .insert(destination.clone(), sum);
+                        let hop = next_hops[source][intermediate].clone();
+                        next_hops
+                            .get_mut(source)
+                            .unwrap()
+                            .insert(destination.clone(), hop);
                     }
                 }
             }
         }
     }
 
-    for &i in &keys {
-        if weight_matrix[&i][&i] < E::default() {
-            return None;
+    Ok(AllPairsShortestPaths {
+        distances: weight_matrix,
+        next_hops,
+    })
+}
+
+/// Rebuilds the negative cycle found by Floyd-Warshall going from source to intermediate and
+/// back. Both halves may share vertices, so the walk is split into simple cycles on every
+/// repeated vertex, and the first negative one is kept
+fn negative_cycle<V, W>(
+    edges: &BTreeMap<V, BTreeMap<V, W>>,
+    next_hops: &BTreeMap<V, BTreeMap<V, V>>,
+    source: &V,
+    intermediate: &V,
+) -> NegativeCycle<V, W>
+where
+    V: GraphVertexTrait,
+    W: GraphEdgeTrait,
+{
+    let hops = |from: &V, to: &V| {
+        let mut hops = vec![from.clone()];
+        let mut current = next_hops[from][to].clone();
+        while current != *to && hops.len() <= next_hops.len() {
+            let next = next_hops[&current][to].clone();
+            hops.push(current);
+            current = next;
+        }
+        hops
+    };
+    let weight_of = |cycle: &[V]| {
+        let mut total = W::default();
+        for (i, from) in cycle.iter().enumerate() {
+            total += edges[from][&cycle[(i + 1) % cycle.len()]];
+        }
+        total
+    };
+
+    let mut walk = hops(source, intermediate);
+    walk.extend(hops(intermediate, source));
+    let mut cycle: Vec<V> = Vec::with_capacity(walk.len());
+    for vertex in walk {
+        if let Some(start) = cycle.iter().position(|seen| *seen == vertex) {
+            let inner = cycle.split_off(start);
+            let total = weight_of(&inner);
+            if total < W::default() {
+                return NegativeCycle::new(inner, total);
+            }
         }
+        cycle.push(vertex);
     }
+    let total = weight_of(&cycle);
+    NegativeCycle::new(cycle, total)
+}
+
+/// Johnson's algorithm
+/// Bellman-Ford from an extra vertex linked to every other one gives a potential for each vertex,
+/// which turns every weight non-negative without changing which paths are the shortest,
+/// then Dijkstra runs from every vertex. Much faster than Floyd-Warshall on sparse graphs.
+/// Fails with the cycle itself when there is a negative one
+pub fn johnson<V, E, G>(graph: &G) -> Result<AllPairsShortestPaths<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + Sub<Output = E>,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    johnson_with(graph, |_, _, value| *value)
+}
 
-    Some(weight_matrix)
+/// Johnson's algorithm over the weights the closure takes from the edge payloads
+pub fn johnson_by<V, E, W, G, F>(
+    graph: &G,
+    weight: F,
+) -> Result<AllPairsShortestPaths<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait + Sub<Output = W>,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&E) -> W,
+{
+    johnson_with(graph, |_, _, value| weight(value))
+}
+
+/// Johnson's algorithm with the weight of each edge computed by the closure from (from, to, value)
+pub fn johnson_with<V, E, W, G, F>(
+    graph: &G,
+    weight: F,
+) -> Result<AllPairsShortestPaths<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait + Sub<Output = W>,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    let potentials = potentials(graph, &weight)?;
+    let mut distances = BTreeMap::new();
+    let mut next_hops = BTreeMap::new();
+
+    // w(u, v) + h(u) - h(v) is never negative, and any path from s to t is
+    // h(s) - h(t) heavier than before, so the shortest ones stay the same
+    let reweighted = |u: &V, v: &V, value: &E| {
+        let mut cost = weight(u, v, value);
+        cost += potentials[u];
+        // Rounding may leave a float slightly below zero
+        (cost - potentials[v]).max(W::default())
+    };
+
+    for source in graph.iter_vertices() {
+        // Reweighted costs are never negative, so there is nothing for Dijkstra to check
+        let paths = dijkstra_unchecked(graph, source.clone(), reweighted);
+        let mut row = BTreeMap::new();
+        for (destination, dist) in paths.distances() {
+            let mut dist = *dist;
+            if dist != W::max_value() {
+                dist += potentials[destination];
+                dist = dist - potentials[source];
+            }
+            row.insert(destination.clone(), dist);
+        }
+        distances.insert(source.clone(), row);
+        next_hops.insert(source.clone(), first_hops(source, paths.predecessors()));
+    }
+
+    Ok(AllPairsShortestPaths {
+        distances,
+        next_hops,
+    })
+}
+
+/// Distances from an extra vertex with a zero weight edge to every other one, found by
+/// running Bellman-Ford with every vertex already at zero. Fails with any negative cycle
+fn potentials<V, E, W, G, F>(graph: &G, weight: &F) -> Result<HashMap<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    let mut potentials = graph
+        .iter_vertices()
+        .map(|vertex| (vertex.clone(), W::default()))
+        .collect();
+    relax_rounds(graph, &mut potentials, &mut HashMap::new(), weight)?;
+    Ok(potentials)
+}
+
+/// Next hop from source towards every vertex it reaches, out of the shortest path tree
+fn first_hops<V: GraphVertexTrait>(source: &V, predecessors: &HashMap<V, V>) -> BTreeMap<V, V> {
+    let mut hops = BTreeMap::from([(source.clone(), source.clone())]);
+    for vertex in predecessors.keys() {
+        // Climb until a vertex whose hop is known, or one right after the source
+        let mut chain = vec![];
+        let mut current = vertex;
+        let hop = loop {
+            if let Some(hop) = hops.get(current) {
+                break hop.clone();
+            }
+            chain.push(current);
+            let previous = &predecessors[current];
+            if previous == source {
+                break current.clone();
+            }
+            current = previous;
+        };
+        for vertex in chain {
+            hops.insert(vertex.clone(), hop.clone());
+        }
+    }
+    hops
 }
 
 #[cfg(test)]
 mod test_single_path {
     use super::floyd_warshall;
+    use super::floyd_warshall_by;
+    use super::floyd_warshall_with;
+    use super::johnson;
+    use super::johnson_by;
+    use super::johnson_with;
+    use crate::GraphError;
     use std::collections::BTreeMap;
 
     #[test]
     fn test_single_edge() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_edge("a", "d", 60);
         graph.add_edge("a", "c", 12);
         graph.add_edge("c", "b", 20);
@@ -99,16 +385,260 @@ mod test_single_path {
 
         let res = floyd_warshall(&graph);
         println!("{:?}", res);
-        assert_eq!(dists_0, res.unwrap());
+        assert_eq!(dists_0, res.unwrap().into_distances());
     }
 
     #[test]
     fn test_detect_negative_cycle() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
+        graph.add_edge(1, 2, 1);
+        graph.add_edge(2, 3, 3);
+        graph.add_edge(3, 4, 2);
+        graph.add_edge(4, 2, -6);
+        let GraphError::NegativeCycle(cycle) = floyd_warshall(&graph).unwrap_err() else {
+            panic!("expected a negative cycle")
+        };
+        assert_eq!(-1, cycle.weight());
+        assert_eq!(3, cycle.cycle().len());
+        assert!(!cycle.contains(1));
+    }
+
+    #[test]
+    fn test_negative_cycle_shapes() {
+        // A positive loop through 2 hangs off the negative one through 3
+        let mut graph = crate::Graph::new();
+        graph.add_edge(0, 1, 1);
+        graph.add_edge(1, 2, 1);
+        graph.add_edge(2, 1, 1);
+        graph.add_edge(1, 3, -1);
+        graph.add_edge(3, 0, -1);
+        let GraphError::NegativeCycle(cycle) = floyd_warshall(&graph).unwrap_err() else {
+            panic!("expected a negative cycle")
+        };
+        assert_eq!(-1, cycle.weight());
+        assert_eq!(3, cycle.cycle().len());
+        assert!(!cycle.contains(2));
+
+        graph.add_edge(2, 2, -1);
+        let GraphError::NegativeCycle(cycle) = floyd_warshall(&graph).unwrap_err() else {
+            panic!("expected a negative cycle")
+        };
+        assert_eq!(&[2], cycle.cycle());
+        assert_eq!(-1, cycle.weight());
+
+        let mut graph = crate::Graph::new_undirected();
+        graph.add_edge("a", "b", 2);
+        graph.add_edge("b", "c", -3);
+        let GraphError::NegativeCycle(cycle) = floyd_warshall(&graph).unwrap_err() else {
+            panic!("expected a negative cycle")
+        };
+        assert_eq!(-6, cycle.weight());
+        assert!(cycle.contains("b") && cycle.contains("c"));
+    }
+
+    #[test]
+    fn test_paths() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge("a", "d", 60);
+        graph.add_edge("a", "c", 12);
+        graph.add_edge("c", "b", 20);
+        graph.add_edge("b", "a", 10);
+        graph.add_edge("c", "d", 50);
+        graph.add_edge("c", "d", 45);
+
+        let res = floyd_warshall(&graph).unwrap();
+        assert_eq!(Some(vec!["a", "c", "d"]), res.path("a", "d"));
+        assert_eq!(Some(57), res.distance("a", "d"));
+        assert_eq!(Some(vec!["c", "b", "a"]), res.path("c", "a"));
+        assert_eq!(Some(vec!["b", "a", "c"]), res.path("b", "c"));
+        assert_eq!(Some(vec!["b"]), res.path("b", "b"));
+        assert_eq!(Some("c"), res.next_hop("a", "b"));
+        assert_eq!(None, res.path("d", "a"));
+        assert_eq!(None, res.path("a", "z"));
+    }
+
+    #[test]
+    fn test_paths_undirected() {
+        let mut graph = crate::Graph::new_undirected();
+        graph.add_edge(0, 1, 4);
+        graph.add_edge(0, 2, 1);
+        graph.add_edge(2, 1, 2);
+        graph.add_edge(2, 3, 5);
+        graph.add_edge(1, 3, 1);
+        graph.add_edge(3, 4, 3);
+
+        let res = floyd_warshall(&graph).unwrap();
+        assert_eq!(Some(vec![0, 2, 1, 3, 4]), res.path(0, 4));
+        assert_eq!(Some(vec![4, 3, 1, 2, 0]), res.path(4, 0));
+        assert_eq!(Some(7), res.distance(4, 0));
+    }
+
+    #[test]
+    fn test_edge_payloads() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge(1, 2, "bus:4");
+        graph.add_edge(2, 3, "bus:-2");
+        graph.add_edge(1, 3, "walk:3");
+
+        let fares = |ticket: &&str| ticket.split(':').nth(1).unwrap().parse::<i32>().unwrap();
+        let res = floyd_warshall_by(&graph, fares).unwrap();
+        assert_eq!(Some(2), res.distance(1, 3));
+        assert_eq!(Some(vec![1, 2, 3]), res.path(1, 3));
+
+        graph.add_edge(3, 1, "bus:-3");
+        let GraphError::NegativeCycle(cycle) = floyd_warshall_by(&graph, fares).unwrap_err() else {
+            panic!("expected a negative cycle")
+        };
+        assert_eq!(-1, cycle.weight());
+    }
+
+    #[test]
+    fn test_weight_closure() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge(0, 1, 10);
+        graph.add_edge(1, 2, 10);
+        graph.add_edge(0, 2, 25);
+
+        // Each hop adds a fixed handover latency
+        let res = floyd_warshall_with(&graph, |_, _, latency| latency + 3).unwrap();
+        assert_eq!(Some(26), res.distance(0, 2));
+        assert_eq!(Some(vec![0, 1, 2]), res.path(0, 2));
+        let res = floyd_warshall_with(&graph, |_, _, latency| latency + 6).unwrap();
+        assert_eq!(Some(31), res.distance(0, 2));
+        assert_eq!(Some(vec![0, 2]), res.path(0, 2));
+    }
+
+    /// Checks johnson against floyd_warshall on the distances, and that every path it rebuilds
+    /// costs that distance. Paths may differ between the two when several are the shortest.
+    fn check_johnson<T: crate::EdgeTypeTrait>(graph: &crate::Graph<i32, i32, T>) {
+        let res = johnson(graph).unwrap();
+        assert_eq!(floyd_warshall(graph).unwrap().distances(), res.distances());
+        for (source, row) in res.distances() {
+            for (destination, distance) in row {
+                let Some(path) = res.path(*source, *destination) else {
+                    assert_eq!(i32::MAX, *distance);
+                    continue;
+                };
+                let cost: i32 = path
+                    .windows(2)
+                    .map(|pair| {
+                        let edges = graph.adj_list()[&pair[0]].iter();
+                        edges
+                            .filter(|(to, _)| *to == pair[1])
+                            .map(|(_, value)| *value)
+                            .min()
+                            .unwrap()
+                    })
+                    .sum();
+                assert_eq!(*distance, cost);
+            }
+        }
+    }
+
+    #[test]
+    fn test_johnson_same_as_floyd_warshall() {
+        // Cormen's example, with negative weights but no negative cycle
+        let mut graph = crate::Graph::new();
+        graph.add_edge(1, 2, 3);
+        graph.add_edge(1, 3, 8);
+        graph.add_edge(1, 5, -4);
+        graph.add_edge(2, 4, 1);
+        graph.add_edge(2, 5, 7);
+        graph.add_edge(3, 2, 4);
+        graph.add_edge(4, 1, 2);
+        graph.add_edge(4, 3, -5);
+        graph.add_edge(5, 4, 6);
+        graph.add_vertex(6);
+
+        check_johnson(&graph);
+        let res = johnson(&graph).unwrap();
+        assert_eq!(Some(-4), res.distance(2, 3));
+        assert_eq!(Some(vec![1, 5, 4, 3, 2]), res.path(1, 2));
+        assert_eq!(Some(i32::MAX), res.distance(1, 6));
+        assert_eq!(None, res.path(6, 1));
+
+        let mut undirected = crate::Graph::new_undirected();
+        undirected.add_edge(0, 1, 4);
+        undirected.add_edge(0, 2, 1);
+        undirected.add_edge(2, 1, 2);
+        undirected.add_edge(2, 3, 5);
+        undirected.add_edge(1, 3, 1);
+        undirected.add_edge(3, 4, 3);
+        check_johnson(&undirected);
+        let res = johnson(&undirected).unwrap();
+        assert_eq!(Some(vec![4, 3, 1, 2, 0]), res.path(4, 0));
+
+        // Two shortest paths from 0 to 3, either one will do
+        undirected.add_edge(0, 3, 4);
+        check_johnson(&undirected);
+
+        let empty: crate::Graph<i32, i32> = crate::Graph::new();
+        assert!(johnson(&empty).unwrap().distances().is_empty());
+    }
+
+    #[test]
+    fn test_johnson_negative_cycle() {
+        let mut graph = crate::Graph::new();
         graph.add_edge(1, 2, 1);
         graph.add_edge(2, 3, 3);
         graph.add_edge(3, 4, 2);
         graph.add_edge(4, 2, -6);
-        assert_eq!(None, floyd_warshall(&graph));
+        let GraphError::NegativeCycle(cycle) = johnson(&graph).unwrap_err() else {
+            panic!("expected a negative cycle")
+        };
+        assert_eq!(-1, cycle.weight());
+        assert_eq!(3, cycle.cycle().len());
+        assert!(!cycle.contains(1));
+
+        // A negative edge on an undirected graph is a negative cycle by itself
+        let mut undirected = crate::Graph::new_undirected();
+        undirected.add_edge("a", "b", 2);
+        undirected.add_edge("b", "c", -1);
+        assert!(matches!(
+            johnson(&undirected),
+            Err(GraphError::NegativeCycle(_))
+        ));
+    }
+
+    #[test]
+    fn test_johnson_weighs_each_edge_once_per_source() {
+        // A sparse ring with shortcuts, every vertex reaches every other one
+        let vertices = 200;
+        let mut graph = crate::Graph::new();
+        for i in 0..vertices {
+            graph.add_edge(i, (i + 1) % vertices, i % 10 + 1);
+            graph.add_edge(i, (i * 13 + 5) % vertices, 3);
+        }
+        let edges = 2 * vertices as usize;
+
+        let calls = std::cell::Cell::new(0);
+        let res = johnson_with(&graph, |_, _, value| {
+            calls.set(calls.get() + 1);
+            *value
+        })
+        .unwrap();
+        assert_eq!(vertices as usize, res.distances().len());
+        // One round to find the potentials, then one weighing per edge and source
+        assert!(calls.get() <= edges * (vertices as usize + 1));
+    }
+
+    #[test]
+    fn test_johnson_float_payloads() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge("a", "b", "0.1");
+        graph.add_edge("b", "c", "-0.3");
+        graph.add_edge("a", "c", "0.2");
+        graph.add_edge("c", "d", "0.7");
+        graph.add_edge("a", "d", "0.5");
+
+        let res = johnson_by(&graph, |value| {
+            crate::OrderedFloat(value.parse::<f64>().unwrap())
+        })
+        .unwrap();
+        let ad = res.distance("a", "d").unwrap().into_inner();
+        assert!((ad - 0.5).abs() < 1e-9);
+        assert_eq!(Some(vec!["a", "b", "c"]), res.path("a", "c"));
+        let bd = res.distance("b", "d").unwrap().into_inner();
+        assert!((bd - 0.4).abs() < 1e-9);
     }
 }
@@ -1,4 +1,410 @@
-use super::{EdgeComparator, EdgeType, Graph, GraphEdgeTrait, GraphVertexTrait};
+use super::single_path::non_negative_weights;
+use super::{dijkstra, GraphEdgeTrait, GraphError, GraphStorage, GraphVertexTrait};
+use num::{CheckedAdd, ToPrimitive};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 
-/// Centrality algorithms.
-/// Degree, Betweenness, Closeness, Eigenvector, PageRank
\ No newline at end of file
+// Centrality algorithms.
+// Degree, Betweenness, Closeness, Eigenvector (PageRank has its own module)
+// All of them can use the edge values as weights, or just count the edges.
+
+/// Degree centrality
+/// The number of edges touching each vertex (incoming and outgoing ones when directed),
+/// divided by the |V| - 1 other vertices it could be linked to.
+/// When weighted, the edge values are summed instead of counted.
+pub fn degree_centrality<V, E, G>(graph: &G, weighted: bool) -> HashMap<V, f64>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + ToPrimitive,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    let mut centrality: HashMap<V, f64> = graph.iter_vertices().map(|v| (v.clone(), 0.0)).collect();
+    for (from, to, &value) in graph.iter_edges() {
+        let weight = edge_weight(value, weighted);
+        *centrality.get_mut(from).unwrap() += weight;
+        if graph.is_directed() {
+            *centrality.get_mut(to).unwrap() += weight;
+        }
+    }
+
+    let others = graph.vertices_count().saturating_sub(1) as f64;
+    if others > 0.0 {
+        for value in centrality.values_mut() {
+            *value /= others;
+        }
+    }
+    centrality
+}
+
+/// Closeness centrality
+/// How close a vertex is to the ones it can reach: (r - 1) / (sum of the distances to them),
+/// scaled by (r - 1) / (|V| - 1) so vertices reaching few others don't look central.
+/// Distances are hop counts (BFS), or the edge values when weighted (Dijkstra),
+/// which fails with NegativeWeight when one of them is negative.
+pub fn closeness_centrality<V, E, G>(
+    graph: &G,
+    weighted: bool,
+) -> Result<HashMap<V, f64>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + ToPrimitive,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    let n = graph.vertices_count();
+    let mut centrality = HashMap::with_capacity(n);
+    for vertex in graph.iter_vertices() {
+        let distances: Vec<f64> = if weighted {
+            dijkstra(graph, vertex.clone())?
+                .into_distances()
+                .into_values()
+                .filter(|distance| *distance != E::max_value())
+                .map(|distance| edge_weight(distance, true))
+                .collect()
+        } else {
+            hop_counts(graph, vertex)
+                .into_values()
+                .map(|hops| hops as f64)
+                .collect()
+        };
+
+        let reached = (distances.len() - 1) as f64;
+        let total: f64 = distances.iter().sum();
+        let closeness = if total > 0.0 && n > 1 {
+            (reached / total) * (reached / (n - 1) as f64)
+        } else {
+            0.0
+        };
+        centrality.insert(vertex.clone(), closeness);
+    }
+    Ok(centrality)
+}
+
+/// Betweenness centrality (Brandes' algorithm)
+/// The share of shortest paths between other pairs of vertices that go through each vertex.
+/// From every source, a BFS (or Dijkstra when weighted) counts the shortest paths to each vertex,
+/// then the dependencies are accumulated back from the farthest vertices: O(VE) unweighted,
+/// O(VE + V² log V) weighted. Normalized by the (|V| - 1)(|V| - 2) possible pairs.
+/// Like Dijkstra, the weighted version fails with NegativeWeight on negative edge values.
+pub fn betweenness_centrality<V, E, G>(
+    graph: &G,
+    weighted: bool,
+) -> Result<HashMap<V, f64>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + ToPrimitive,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    if weighted {
+        non_negative_weights(graph, &|_: &V, _: &V, value: &E| *value)?;
+    }
+    let mut centrality: HashMap<V, f64> = graph.iter_vertices().map(|v| (v.clone(), 0.0)).collect();
+
+    for source in graph.iter_vertices() {
+        let (order, predecessors, paths) = if weighted {
+            shortest_path_dag_weighted(graph, source)
+        } else {
+            shortest_path_dag(graph, source)
+        };
+
+        let mut dependency: HashMap<&V, f64> = order.iter().map(|v| (*v, 0.0)).collect();
+        for w in order.iter().rev() {
+            for v in predecessors.get(w).into_iter().flatten() {
+                let share = paths[v] / paths[w] * (1.0 + dependency[w]);
+                *dependency.get_mut(v).unwrap() += share;
+            }
+            if *w != source {
+                *centrality.get_mut(*w).unwrap() += dependency[w];
+            }
+        }
+    }
+
+    // Undirected graphs store both directions, so each pair was counted twice, which the
+    // normalization for undirected graphs, 2 / ((n - 1)(n - 2)), takes into account.
+    let n = graph.vertices_count() as f64;
+    if n > 2.0 {
+        for value in centrality.values_mut() {
+            *value /= (n - 1.0) * (n - 2.0);
+        }
+    }
+    Ok(centrality)
+}
+
+/// Eigenvector centrality
+/// A vertex is central when it is linked from other central vertices: power iteration over
+/// the (incoming) adjacency matrix until the scores move less than epsilon, or max_iterations.
+/// Scores are scaled to unit length. When weighted, each link counts as much as its edge value.
+pub fn eigenvector_centrality<V, E, G>(
+    graph: &G,
+    weighted: bool,
+    epsilon: f64,
+    max_iterations: usize,
+) -> HashMap<V, f64>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + ToPrimitive,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    let n = graph.vertices_count();
+    let mut centrality: HashMap<V, f64> = graph
+        .iter_vertices()
+        .map(|v| (v.clone(), 1.0 / n as f64))
+        .collect();
+
+    for _ in 0..max_iterations {
+        // Starting from the previous scores is the same as iterating over A + I,
+        // which keeps bipartite graphs from oscillating forever
+        let mut next = centrality.clone();
+        for (from, to, &value) in graph.iter_edges() {
+            *next.get_mut(to).unwrap() += centrality[from] * edge_weight(value, weighted);
+        }
+
+        let norm = next.values().map(|x| x * x).sum::<f64>().sqrt();
+        if norm == 0.0 {
+            return next;
+        }
+        for value in next.values_mut() {
+            *value /= norm;
+        }
+
+        let diff: f64 = next
+            .iter()
+            .map(|(vertex, value)| (value - centrality[vertex]).abs())
+            .sum();
+        centrality = next;
+        if diff < n as f64 * epsilon {
+            break;
+        }
+    }
+    centrality
+}
+
+fn edge_weight<E: GraphEdgeTrait + ToPrimitive>(value: E, weighted: bool) -> f64 {
+    if weighted {
+        value.to_f64().unwrap_or(f64::INFINITY)
+    } else {
+        1.0
+    }
+}
+
+fn hop_counts<'a, V, G>(graph: &'a G, start: &'a V) -> HashMap<&'a V, usize>
+where
+    V: GraphVertexTrait,
+    G: GraphStorage<Vertex = V>,
+{
+    let mut hops = HashMap::new();
+    let mut queue = VecDeque::new();
+    hops.insert(start, 0);
+    queue.push_back(start);
+    while let Some(u) = queue.pop_front() {
+        for (v, _) in graph.neighbours(u) {
+            if !hops.contains_key(v) {
+                hops.insert(v, hops[u] + 1);
+                queue.push_back(v);
+            }
+        }
+    }
+    hops
+}
+
+type ShortestPathDag<'a, V> = (Vec<&'a V>, HashMap<&'a V, Vec<&'a V>>, HashMap<&'a V, f64>);
+
+/// BFS from source returning the vertices in visiting order, the predecessors of each one
+/// on its shortest paths and how many shortest paths reach it
+fn shortest_path_dag<'a, V, G>(graph: &'a G, source: &'a V) -> ShortestPathDag<'a, V>
+where
+    V: GraphVertexTrait,
+    G: GraphStorage<Vertex = V>,
+{
+    let mut order = Vec::new();
+    let mut predecessors: HashMap<&V, Vec<&V>> = HashMap::new();
+    let mut paths = HashMap::from([(source, 1.0)]);
+    let mut hops = HashMap::from([(source, 0)]);
+    let mut queue = VecDeque::from([source]);
+
+    while let Some(v) = queue.pop_front() {
+        order.push(v);
+        for (w, _) in graph.neighbours(v) {
+            if !hops.contains_key(w) {
+                hops.insert(w, hops[v] + 1);
+                queue.push_back(w);
+            }
+            if hops[w] == hops[v] + 1 {
+                *paths.entry(w).or_insert(0.0) += paths[v];
+                predecessors.entry(w).or_default().push(v);
+            }
+        }
+    }
+    (order, predecessors, paths)
+}
+
+/// Same as shortest_path_dag, using Dijkstra over the edge values
+fn shortest_path_dag_weighted<'a, V, G>(graph: &'a G, source: &'a V) -> ShortestPathDag<'a, V>
+where
+    V: GraphVertexTrait,
+    G: GraphStorage<Vertex = V>,
+    G::Edge: GraphEdgeTrait,
+{
+    let mut order = Vec::new();
+    let mut predecessors: HashMap<&V, Vec<&V>> = HashMap::new();
+    let mut paths = HashMap::from([(source, 1.0)]);
+    let mut distances = HashMap::from([(source, G::Edge::default())]);
+    let mut settled = HashSet::new();
+    let mut prio = BinaryHeap::from([Reverse((G::Edge::default(), source))]);
+
+    while let Some(Reverse((dist, v))) = prio.pop() {
+        if !settled.insert(v) {
+            continue;
+        }
+        order.push(v);
+        for (w, weight) in graph.neighbours(v) {
+            let next = match dist.checked_add(weight) {
+                Some(next) => next,
+                None => continue,
+            };
+            match distances.get(w) {
+                Some(current) if next > *current => {}
+                Some(current) if next == *current => {
+                    if !settled.contains(w) {
+                        *paths.get_mut(w).unwrap() += paths[v];
+                        predecessors.entry(w).or_default().push(v);
+                    }
+                }
+                _ => {
+                    distances.insert(w, next);
+                    paths.insert(w, paths[v]);
+                    predecessors.insert(w, vec![v]);
+                    prio.push(Reverse((next, w)));
+                }
+            }
+        }
+    }
+    (order, predecessors, paths)
+}
+
+#[cfg(test)]
+mod test_centrality {
+    use super::{
+        betweenness_centrality, closeness_centrality, degree_centrality, eigenvector_centrality,
+    };
+    use crate::Graph;
+
+    fn assert_close(expected: f64, value: f64) {
+        assert!(
+            (expected - value).abs() < 1e-6,
+            "expected {} got {}",
+            expected,
+            value
+        );
+    }
+
+    fn path_graph() -> Graph<i32, i32, crate::Undirected> {
+        let mut graph = Graph::new_undirected();
+        graph.add_edge(1, 2, 1);
+        graph.add_edge(2, 3, 1);
+        graph
+    }
+
+    #[test]
+    fn test_degree_centrality() {
+        let graph = path_graph();
+        let degree = degree_centrality(&graph, false);
+        assert_close(0.5, degree[&1]);
+        assert_close(1.0, degree[&2]);
+
+        let mut graph = Graph::new();
+        graph.add_edge("A", "B", 3);
+        graph.add_edge("A", "C", 2);
+        graph.add_edge("C", "A", 5);
+        let degree = degree_centrality(&graph, false);
+        assert_close(1.5, degree[&"A"]);
+        assert_close(0.5, degree[&"B"]);
+        let strength = degree_centrality(&graph, true);
+        assert_close(5.0, strength[&"A"]);
+        assert_close(3.5, strength[&"C"]);
+    }
+
+    #[test]
+    fn test_closeness_centrality() {
+        let graph = path_graph();
+        let closeness = closeness_centrality(&graph, false).unwrap();
+        assert_close(2.0 / 3.0, closeness[&1]);
+        assert_close(1.0, closeness[&2]);
+
+        // 1 only reaches 2 directly, so it sits right next to half of the graph
+        let mut graph = Graph::new();
+        graph.add_edge(1, 2, 4);
+        graph.add_edge(3, 1, 1);
+        let closeness = closeness_centrality(&graph, false).unwrap();
+        assert_close(0.5, closeness[&1]);
+        assert_close(0.0, closeness[&2]);
+        assert_close(2.0 / 3.0, closeness[&3]);
+        let closeness = closeness_centrality(&graph, true).unwrap();
+        assert_close(0.125, closeness[&1]);
+        assert_close(2.0 / 6.0, closeness[&3]);
+    }
+
+    #[test]
+    fn test_betweenness_centrality() {
+        let graph = path_graph();
+        let betweenness = betweenness_centrality(&graph, false).unwrap();
+        assert_close(0.0, betweenness[&1]);
+        assert_close(1.0, betweenness[&2]);
+        assert_close(0.0, betweenness[&3]);
+
+        // Both middle vertices carry half of the shortest paths from A to D
+        let mut graph = Graph::new();
+        graph.add_edge("A", "B", 1);
+        graph.add_edge("A", "C", 1);
+        graph.add_edge("B", "D", 1);
+        graph.add_edge("C", "D", 1);
+        let betweenness = betweenness_centrality(&graph, false).unwrap();
+        assert_close(0.5 / 6.0, betweenness[&"B"]);
+        assert_close(0.5 / 6.0, betweenness[&"C"]);
+        assert_close(0.0, betweenness[&"A"]);
+        let betweenness = betweenness_centrality(&graph, true).unwrap();
+        assert_close(0.5 / 6.0, betweenness[&"B"]);
+    }
+
+    #[test]
+    fn test_betweenness_centrality_weighted() {
+        let mut graph = Graph::new_undirected();
+        graph.add_edge("A", "B", 1);
+        graph.add_edge("B", "C", 1);
+        graph.add_edge("A", "C", 5);
+
+        let unweighted = betweenness_centrality(&graph, false).unwrap();
+        assert_close(0.0, unweighted[&"B"]);
+        let weighted = betweenness_centrality(&graph, true).unwrap();
+        assert_close(1.0, weighted[&"B"]);
+        assert_close(0.0, weighted[&"A"]);
+    }
+
+    #[test]
+    fn test_eigenvector_centrality() {
+        let graph = path_graph();
+        let eigenvector = eigenvector_centrality(&graph, false, 1e-9, 1000);
+        assert_close(0.5, eigenvector[&1]);
+        assert_close(1.0 / 2.0_f64.sqrt(), eigenvector[&2]);
+        assert_close(0.5, eigenvector[&3]);
+
+        // The heavier edge pulls the score towards its end
+        let mut graph = Graph::new_undirected();
+        graph.add_edge("A", "B", 1);
+        graph.add_edge("B", "C", 4);
+        let eigenvector = eigenvector_centrality(&graph, true, 1e-9, 1000);
+        assert!(eigenvector[&"C"] > eigenvector[&"A"]);
+        assert!(eigenvector[&"B"] > eigenvector[&"C"]);
+    }
+
+    #[test]
+    fn test_negative_weights() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge(1, 2, 3);
+        graph.add_edge(2, 3, -1);
+
+        let rejected = Err(crate::GraphError::NegativeWeight(2, 3, -1));
+        assert_eq!(rejected, closeness_centrality(&graph, true));
+        assert_eq!(rejected, betweenness_centrality(&graph, true));
+        assert!(betweenness_centrality(&graph, false).is_ok());
+    }
+}
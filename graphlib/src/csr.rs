@@ -0,0 +1,297 @@
+use super::{Directed, EdgeTypeTrait, Graph, GraphPayloadTrait, GraphStorage, GraphVertexTrait};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+// Compressed sparse row representation, built once from a Graph with `Graph::freeze`.
+// The edges of every vertex sit next to each other in flat vectors, addressed by dense
+// usize indices, so walking them costs no tree lookups. The searches and Dijkstra run on
+// those indices directly, with vectors for what they track. It can't be changed afterwards.
+
+/// Immutable graph in compressed sparse row form.
+/// Vertex i is `keys[i]` (in ascending order), and its edges go to `targets[offsets[i]..offsets[i + 1]]`
+/// with the values at the same positions of `weights`.
+#[derive(Debug)]
+pub struct CsrGraph<V: GraphVertexTrait, E: GraphPayloadTrait, T = Directed> {
+    keys: Vec<V>,
+    index: HashMap<V, usize>,
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    weights: Vec<E>,
+    phantom: PhantomData<T>,
+}
+
+impl<V, E, T> CsrGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    /// Dense index of the vertex, from 0 to |V| - 1
+    pub fn index_of(&self, vertex: &V) -> Option<usize> {
+        self.index.get(vertex).copied()
+    }
+
+    /// The vertex behind a dense index
+    pub fn vertex(&self, index: usize) -> Option<&V> {
+        self.keys.get(index)
+    }
+
+    /// Indices and values of the edges leaving the vertex with the given index
+    pub fn neighbour_indices(&self, index: usize) -> (&[usize], &[E]) {
+        if index >= self.keys.len() {
+            return (&[], &[]);
+        }
+        let range = self.offsets[index]..self.offsets[index + 1];
+        (&self.targets[range.clone()], &self.weights[range])
+    }
+
+    pub fn out_degree(&self, vertex: V) -> usize {
+        self.index_of(&vertex)
+            .map_or(0, |i| self.offsets[i + 1] - self.offsets[i])
+    }
+}
+
+impl<V, E, T, N> From<&Graph<V, E, T, N>> for CsrGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    fn from(graph: &Graph<V, E, T, N>) -> Self {
+        let keys: Vec<V> = graph.iter_vertices().cloned().collect();
+        let index: HashMap<V, usize> = keys.iter().cloned().zip(0..).collect();
+        let mut offsets = Vec::with_capacity(keys.len() + 1);
+        let mut targets = Vec::new();
+        let mut weights = Vec::new();
+        offsets.push(0);
+        for vertex in &keys {
+            for (to, value) in graph.neighbours(vertex) {
+                targets.push(index[to]);
+                weights.push(value.clone());
+            }
+            offsets.push(targets.len());
+        }
+        CsrGraph {
+            keys,
+            index,
+            offsets,
+            targets,
+            weights,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<V, E, T> GraphStorage for CsrGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    type Vertex = V;
+    type Edge = E;
+    type EdgeType = T;
+
+    fn vertices_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn contains_vertex(&self, vertex: &V) -> bool {
+        self.index.contains_key(vertex)
+    }
+
+    fn iter_vertices(&self) -> impl Iterator<Item = &V> {
+        self.keys.iter()
+    }
+
+    fn neighbours(&self, vertex: &V) -> impl Iterator<Item = (&V, &E)> {
+        let (targets, weights) = match self.index_of(vertex) {
+            Some(i) => self.neighbour_indices(i),
+            None => (&[][..], &[][..]),
+        };
+        targets
+            .iter()
+            .zip(weights)
+            .map(|(to, value)| (&self.keys[*to], value))
+    }
+
+    fn iter_edges(&self) -> impl Iterator<Item = (&V, &V, &E)> {
+        (0..self.keys.len()).flat_map(move |from| {
+            let range = self.offsets[from]..self.offsets[from + 1];
+            range.map(move |i| {
+                (
+                    &self.keys[from],
+                    &self.keys[self.targets[i]],
+                    &self.weights[i],
+                )
+            })
+        })
+    }
+
+    fn edges_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    fn as_csr(&self) -> Option<&CsrGraph<V, E, T>> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod test_csr {
+    use crate::{
+        bellman_ford, breadth_first_search, depth_first_search, dijkstra, Graph, GraphStorage,
+    };
+
+    fn cities() -> Graph<&'static str, u32> {
+        let mut graph = Graph::new();
+        graph.add_edge("NYC", "MTL", 530);
+        graph.add_edge("NYC", "TOR", 560);
+        graph.add_edge("NYC", "TOR", 600);
+        graph.add_edge("MTL", "TOR", 525);
+        graph.add_edge("TOR", "CHI", 840);
+        graph.add_vertex("SEA");
+        graph
+    }
+
+    #[test]
+    fn test_freeze() {
+        let graph = cities();
+        let frozen = graph.freeze();
+        assert_eq!(graph.vertices_count(), frozen.vertices_count());
+        assert_eq!(graph.edges().len(), frozen.edges_count());
+        assert_eq!(
+            graph.iter_edges().collect::<Vec<_>>(),
+            frozen.iter_edges().collect::<Vec<_>>()
+        );
+
+        let nyc = frozen.index_of(&"NYC").unwrap();
+        assert_eq!(Some(&"NYC"), frozen.vertex(nyc));
+        let (targets, weights) = frozen.neighbour_indices(nyc);
+        assert_eq!(vec![&"MTL", &"TOR", &"TOR"], {
+            targets
+                .iter()
+                .map(|t| frozen.vertex(*t).unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(&[530, 560, 600], weights);
+        assert_eq!(3, frozen.out_degree("NYC"));
+        assert_eq!(0, frozen.out_degree("SEA"));
+        assert_eq!(None, frozen.index_of(&"LAX"));
+        assert_eq!(0, frozen.neighbours(&"LAX").count());
+
+        let mut undirected = Graph::new_undirected();
+        undirected.add_edge(1, 2, 3);
+        let frozen = undirected.freeze();
+        assert!(!frozen.is_directed());
+        assert_eq!(2, frozen.edges_count());
+    }
+
+    #[test]
+    fn test_frozen_algorithms() {
+        let graph = cities();
+        let frozen = graph.freeze();
+        assert_eq!(
+            dijkstra(&graph, "NYC").unwrap(),
+            dijkstra(&frozen, "NYC").unwrap()
+        );
+        assert_eq!(
+            bellman_ford(&graph, "NYC").unwrap(),
+            bellman_ford(&frozen, "NYC").unwrap()
+        );
+        assert_eq!(
+            breadth_first_search(&graph, "NYC", "CHI"),
+            breadth_first_search(&frozen, "NYC", "CHI")
+        );
+        assert_eq!(
+            depth_first_search(&graph, "NYC", "CHI"),
+            depth_first_search(&frozen, "NYC", "CHI")
+        );
+        let paths = dijkstra(&frozen, "NYC").unwrap();
+        assert_eq!(Some(&1400), paths.distances().get("CHI"));
+        assert_eq!(Some(vec!["NYC", "TOR", "CHI"]), paths.path_to("CHI"));
+    }
+
+    #[test]
+    fn test_frozen_algorithms_with_ties() {
+        // Many equal weights, so the results depend on the order edges are walked in
+        let mut graph = Graph::new_undirected();
+        for i in 0..60u32 {
+            graph.add_edge(i, (i * 7 + 3) % 60, i % 3 + 1);
+            graph.add_edge(i, (i * 11 + 1) % 60, 2);
+        }
+        let frozen = graph.freeze();
+        for start in [0, 17, 42] {
+            assert_eq!(
+                dijkstra(&graph, start).unwrap(),
+                dijkstra(&frozen, start).unwrap()
+            );
+            for target in [5, 31, 59] {
+                assert_eq!(
+                    breadth_first_search(&graph, start, target),
+                    breadth_first_search(&frozen, start, target)
+                );
+                assert_eq!(
+                    depth_first_search(&graph, start, target),
+                    depth_first_search(&frozen, start, target)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_frozen_algorithms_skip_vertex_lookups() {
+        use std::cell::Cell;
+        use std::hash::{Hash, Hasher};
+
+        thread_local! {
+            static HASHED: Cell<usize> = const { Cell::new(0) };
+        }
+
+        // A vertex that counts how many times it is hashed
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+        struct Stop(u32);
+        impl Hash for Stop {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                HASHED.with(|hashed| hashed.set(hashed.get() + 1));
+                self.0.hash(state);
+            }
+        }
+        let hashed_by = |run: &dyn Fn()| {
+            HASHED.with(|hashed| hashed.set(0));
+            run();
+            HASHED.with(Cell::get)
+        };
+
+        let vertices = 100;
+        let mut graph = Graph::new();
+        for i in 0..vertices {
+            for step in 1..=10 {
+                graph.add_edge(Stop(i), Stop((i + step) % vertices), step);
+            }
+        }
+        let frozen = graph.freeze();
+        let edges = frozen.edges_count();
+
+        // Only the endpoints and the final maps are hashed, never a neighbour
+        let dijkstra_hashes = hashed_by(&|| {
+            dijkstra(&frozen, Stop(0)).unwrap();
+        });
+        assert!(dijkstra_hashes <= 3 * vertices as usize);
+        let bfs_hashes = hashed_by(&|| {
+            breadth_first_search(&frozen, Stop(0), Stop(vertices - 1)).unwrap();
+        });
+        assert!(bfs_hashes <= 4);
+        let dfs_hashes = hashed_by(&|| {
+            depth_first_search(&frozen, Stop(0), Stop(vertices - 1)).unwrap();
+        });
+        assert!(dfs_hashes <= 4);
+
+        // The Graph itself looks up every neighbour it relaxes
+        assert!(
+            hashed_by(&|| {
+                dijkstra(&graph, Stop(0)).unwrap();
+            }) > edges
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use super::{
+    Directed, EdgeTypeTrait, Graph, GraphPayloadTrait, GraphStorage, GraphVertexTrait, Undirected,
+};
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+
+// Edge list representation: the plainest one, a vector of (from, to, value).
+// Cheap to build and to walk edge by edge, as Bellman-Ford and Kruskal do.
+
+/// Graph stored as a list of (from, to, value) edges plus its set of vertices.
+/// The list is kept sorted by source vertex, so the edges leaving a vertex are found
+/// with a binary search. It keeps every edge added, parallel or identical, and
+/// undirected edges are stored once from each end, as in `Graph`.
+#[derive(Debug, Default)]
+pub struct EdgeListGraph<V: GraphVertexTrait, E: GraphPayloadTrait, T = Directed> {
+    vertices: BTreeSet<V>,
+    edges: Vec<(V, V, E)>,
+    phantom: PhantomData<T>,
+}
+
+impl<V, E> EdgeListGraph<V, E, Directed>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+{
+    pub fn new() -> Self {
+        EdgeListGraph::empty()
+    }
+}
+
+impl<V, E> EdgeListGraph<V, E, Undirected>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+{
+    pub fn new_undirected() -> Self {
+        EdgeListGraph::empty()
+    }
+}
+
+impl<V, E, T> EdgeListGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    fn empty() -> Self {
+        EdgeListGraph {
+            vertices: BTreeSet::new(),
+            edges: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn add_vertex(&mut self, vertex: V) {
+        self.vertices.insert(vertex);
+    }
+
+    /// Inserts the edge after the others leaving the same vertex, O(E)
+    pub fn add_edge(&mut self, from: V, to: V, value: E) {
+        self.add_vertex(from.clone());
+        self.add_vertex(to.clone());
+        if !T::is_directed() && from != to {
+            self.insert(to.clone(), from.clone(), value.clone());
+        }
+        self.insert(from, to, value);
+    }
+
+    fn insert(&mut self, from: V, to: V, value: E) {
+        let at = self.edges.partition_point(|(v, _, _)| *v <= from);
+        self.edges.insert(at, (from, to, value));
+    }
+
+    /// Removes the edges going from one vertex to the other (both directions when undirected),
+    /// including parallel ones. Returns false when there was no such edge.
+    pub fn remove_edge(&mut self, from: V, to: V) -> bool {
+        let before = self.edges.len();
+        self.edges.retain(|(f, t, _)| {
+            !((*f == from && *t == to) || (!T::is_directed() && *f == to && *t == from))
+        });
+        before != self.edges.len()
+    }
+
+    /// Every edge, sorted by source vertex
+    pub fn edges(&self) -> &[(V, V, E)] {
+        &self.edges
+    }
+
+    pub fn contains(&self, vertex: V) -> bool {
+        self.vertices.contains(&vertex)
+    }
+
+    /// The edges leaving vertex, as a slice of the list
+    fn outgoing(&self, vertex: &V) -> &[(V, V, E)] {
+        let start = self.edges.partition_point(|(v, _, _)| v < vertex);
+        let end = self.edges.partition_point(|(v, _, _)| v <= vertex);
+        &self.edges[start..end]
+    }
+}
+
+impl<V, E, T, N> From<&Graph<V, E, T, N>> for EdgeListGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    fn from(graph: &Graph<V, E, T, N>) -> Self {
+        EdgeListGraph {
+            vertices: graph.iter_vertices().cloned().collect(),
+            // Graph::edges already comes grouped by ascending source vertex
+            edges: graph.edges(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<V, E> FromIterator<(V, V, E)> for EdgeListGraph<V, E, Directed>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+{
+    fn from_iter<I: IntoIterator<Item = (V, V, E)>>(edges: I) -> Self {
+        let mut graph = EdgeListGraph::new();
+        for (from, to, value) in edges {
+            graph.add_vertex(from.clone());
+            graph.add_vertex(to.clone());
+            graph.edges.push((from, to, value));
+        }
+        // Stable, so parallel edges keep the order they came in
+        graph.edges.sort_by(|a, b| a.0.cmp(&b.0));
+        graph
+    }
+}
+
+impl<V, E, T> GraphStorage for EdgeListGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    type Vertex = V;
+    type Edge = E;
+    type EdgeType = T;
+
+    fn vertices_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    fn contains_vertex(&self, vertex: &V) -> bool {
+        self.vertices.contains(vertex)
+    }
+
+    fn iter_vertices(&self) -> impl Iterator<Item = &V> {
+        self.vertices.iter()
+    }
+
+    fn neighbours(&self, vertex: &V) -> impl Iterator<Item = (&V, &E)> {
+        self.outgoing(vertex)
+            .iter()
+            .map(|(_, to, value)| (to, value))
+    }
+
+    fn iter_edges(&self) -> impl Iterator<Item = (&V, &V, &E)> {
+        self.edges.iter().map(|(from, to, value)| (from, to, value))
+    }
+
+    fn edges_count(&self) -> usize {
+        self.edges.len()
+    }
+}
+
+#[cfg(test)]
+mod test_edge_list {
+    use super::EdgeListGraph;
+    use crate::{
+        bellman_ford, breadth_first_search, kruskal, topological_sort, Graph, GraphStorage,
+    };
+
+    #[test]
+    fn test_edge_list_edges() {
+        let mut graph = EdgeListGraph::new();
+        graph.add_edge(3, 1, 7);
+        graph.add_edge(1, 2, 8);
+        graph.add_edge(1, 2, 8);
+        graph.add_edge(2, 3, 1);
+        graph.add_edge(1, 3, 9);
+        graph.add_vertex(4);
+        assert_eq!(4, graph.vertices_count());
+        assert_eq!(
+            vec![(1, 2, 8), (1, 2, 8), (1, 3, 9), (2, 3, 1), (3, 1, 7)],
+            graph.edges()
+        );
+        assert_eq!(
+            vec![(&2, &8), (&2, &8), (&3, &9)],
+            graph.neighbours(&1).collect::<Vec<_>>()
+        );
+        assert_eq!(0, graph.neighbours(&4).count());
+        assert!(graph.remove_edge(1, 2));
+        assert!(!graph.remove_edge(1, 2));
+        assert_eq!(3, graph.edges_count());
+
+        let mut graph = EdgeListGraph::new_undirected();
+        graph.add_edge("B", "A", 1);
+        graph.add_edge("A", "C", 2);
+        assert_eq!(
+            vec![("A", "B", 1), ("A", "C", 2), ("B", "A", 1), ("C", "A", 2)],
+            graph.edges()
+        );
+        assert!(graph.remove_edge("A", "B"));
+        assert_eq!(vec![("A", "C", 2), ("C", "A", 2)], graph.edges());
+        assert!(graph.contains("B"));
+    }
+
+    #[test]
+    fn test_edge_list_from_graph() {
+        let mut graph = Graph::new();
+        graph.add_edge("NYC", "MTL", 530);
+        graph.add_edge("MTL", "TOR", 525);
+        graph.add_vertex("SEA");
+        let list = EdgeListGraph::from(&graph);
+        assert_eq!(4, list.vertices_count());
+        assert_eq!(
+            graph.iter_edges().collect::<Vec<_>>(),
+            list.iter_edges().collect::<Vec<_>>()
+        );
+
+        let collected: EdgeListGraph<_, _> = graph.edges().into_iter().collect();
+        assert_eq!(list.edges(), collected.edges());
+    }
+
+    #[test]
+    fn test_edge_list_algorithms() {
+        let list: EdgeListGraph<_, _> = vec![(1, 2, 4), (2, 3, -2), (1, 3, 5), (3, 4, 1)]
+            .into_iter()
+            .collect();
+        let paths = bellman_ford(&list, 1).unwrap();
+        assert_eq!(Some(&3), paths.distances().get(&4));
+        assert_eq!(Some(vec![1, 2, 3, 4]), paths.path_to(4));
+        assert_eq!(Ok(vec![1, 2, 3, 4]), topological_sort(&list));
+        assert_eq!(Ok(vec![1, 2, 3]), breadth_first_search(&list, 1, 3));
+
+        let mut undirected = EdgeListGraph::new_undirected();
+        undirected.add_edge("A", "B", 2);
+        undirected.add_edge("B", "C", 3);
+        undirected.add_edge("A", "C", 4);
+        let (weight, tree) = kruskal(&undirected).unwrap();
+        assert_eq!(5, weight);
+        assert_eq!(2, tree.len());
+    }
+}
@@ -0,0 +1,115 @@
+use super::{GraphEdgeTrait, GraphVertexTrait};
+use std::error::Error;
+use std::fmt;
+
+/// A cycle whose total weight is negative, which makes shortest paths undefined.
+/// The vertices are in traversal order, and the last one links back to the first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegativeCycle<V: GraphVertexTrait, E: GraphEdgeTrait> {
+    cycle: Vec<V>,
+    weight: E,
+}
+
+impl<V, E> NegativeCycle<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+    pub fn new(cycle: Vec<V>, weight: E) -> Self {
+        NegativeCycle { cycle, weight }
+    }
+
+    pub fn cycle(&self) -> &[V] {
+        &self.cycle
+    }
+
+    pub fn weight(&self) -> E {
+        self.weight
+    }
+
+    pub fn contains(&self, vertex: V) -> bool {
+        self.cycle.contains(&vertex)
+    }
+}
+
+impl<V, E> fmt::Display for NegativeCycle<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "negative cycle {:?} with total weight {:?}",
+            self.cycle, self.weight
+        )
+    }
+}
+
+impl<V, E> Error for NegativeCycle<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+}
+
+/// Why an algorithm couldn't give a result for the graph and arguments it was given
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError<V: GraphVertexTrait, E: GraphEdgeTrait> {
+    /// A vertex passed as argument isn't in the graph
+    MissingVertex(V),
+    /// The target can't be reached from the start
+    Unreachable(V),
+    /// Flows need the source and the sink to be different vertices
+    SameSourceAndSink(V),
+    /// The edge (from, to, weight) is negative, and the algorithm only takes non-negative weights
+    NegativeWeight(V, V, E),
+    NegativeCycle(NegativeCycle<V, E>),
+    /// The algorithm needs at least one vertex
+    EmptyGraph,
+    /// The graph has a cycle, so it can't be topologically sorted
+    NotADag,
+}
+
+impl<V, E> From<NegativeCycle<V, E>> for GraphError<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+    fn from(cycle: NegativeCycle<V, E>) -> Self {
+        GraphError::NegativeCycle(cycle)
+    }
+}
+
+impl<V, E> fmt::Display for GraphError<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::MissingVertex(vertex) => {
+                write!(f, "vertex {:?} is not in the graph", vertex)
+            }
+            GraphError::Unreachable(vertex) => write!(f, "vertex {:?} can't be reached", vertex),
+            GraphError::SameSourceAndSink(vertex) => {
+                write!(f, "vertex {:?} is both the source and the sink", vertex)
+            }
+            GraphError::NegativeWeight(from, to, weight) => write!(
+                f,
+                "edge {:?} -> {:?} has negative weight {:?}",
+                from, to, weight
+            ),
+            GraphError::NegativeCycle(cycle) => cycle.fmt(f),
+            GraphError::EmptyGraph => write!(f, "the graph has no vertices"),
+            GraphError::NotADag => write!(f, "the graph has a cycle"),
+        }
+    }
+}
+
+impl<V, E> Error for GraphError<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+}
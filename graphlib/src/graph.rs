@@ -1,5 +1,7 @@
+use super::{CsrGraph, GraphStorage};
 use num::traits::CheckedAdd;
 use num::Bounded;
+use std::borrow::Borrow;
 use std::cmp::PartialOrd;
 use std::collections::{BTreeMap, HashSet};
 use std::fmt::Debug;
@@ -38,15 +40,39 @@ impl<T> GraphEdgeTrait for T where
 {
 }
 
-pub trait GraphVertexTrait: Debug + Hash + Clone + Copy + PartialOrd + Ord {}
-impl<T> GraphVertexTrait for T where T: Debug + Hash + Clone + Copy + PartialOrd + Ord {}
+pub trait GraphVertexTrait: Debug + Hash + Clone + PartialOrd + Ord {}
+impl<T> GraphVertexTrait for T where T: Debug + Hash + Clone + PartialOrd + Ord {}
 
-#[derive(Debug, Default, Eq, PartialEq)]
-pub struct Graph<V: GraphVertexTrait, E: GraphEdgeTrait, T = Directed> {
+/// What a graph can carry on its edges: the weight itself, or any data the weights are taken from
+pub trait GraphPayloadTrait: Debug + Clone + PartialEq {}
+impl<T> GraphPayloadTrait for T where T: Debug + Clone + PartialEq {}
+
+#[derive(Debug, Default)]
+pub struct Graph<V: GraphVertexTrait, E: GraphPayloadTrait, T = Directed, N = ()> {
     adj_list: BTreeMap<V, Vec<(V, E)>>,
+    // Reverse adjacency, only kept on demand for directed graphs
+    in_list: Option<BTreeMap<V, Vec<(V, E)>>>,
+    // Edges by id, only kept for multigraphs
+    edge_ids: Option<BTreeMap<EdgeId, (V, V, E)>>,
+    next_edge_id: usize,
+    // Payloads of the vertices that were given one
+    vertex_data: BTreeMap<V, N>,
     phantom: PhantomData<T>, //Hackish variable to make rustc keep quiet about T
 }
 
+// The in-edges index is just a cache, two graphs are the same when their edges and payloads are
+impl<V: GraphVertexTrait, E: GraphPayloadTrait, T, N: PartialEq> PartialEq for Graph<V, E, T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.adj_list == other.adj_list && self.vertex_data == other.vertex_data
+    }
+}
+
+impl<V: GraphVertexTrait, E: GraphPayloadTrait + Eq, T, N: Eq> Eq for Graph<V, E, T, N> {}
+
+/// Stable identifier of an edge in a multigraph, unaffected by other edges coming and going
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EdgeId(usize);
+
 #[derive(Debug)]
 pub enum Directed {}
 
@@ -72,26 +98,30 @@ impl EdgeTypeTrait for Undirected {
 impl<V, E> Graph<V, E, Directed>
 where
     V: GraphVertexTrait,
-    E: GraphEdgeTrait,
+    E: GraphPayloadTrait,
 {
     pub fn new() -> Self {
-        Graph {
-            adj_list: BTreeMap::new(),
-            phantom: PhantomData,
-        }
+        Graph::with_mode(false)
+    }
+
+    /// A directed graph keeping every edge added, parallel or identical, each with its own EdgeId
+    pub fn new_multigraph() -> Self {
+        Graph::with_mode(true)
     }
 }
 
 impl<V, E> Graph<V, E, Undirected>
 where
     V: GraphVertexTrait,
-    E: GraphEdgeTrait,
+    E: GraphPayloadTrait,
 {
     pub fn new_undirected() -> Self {
-        Graph {
-            adj_list: BTreeMap::new(),
-            phantom: PhantomData,
-        }
+        Graph::with_mode(false)
+    }
+
+    /// An undirected graph keeping every edge added, parallel or identical, each with its own EdgeId
+    pub fn new_undirected_multigraph() -> Self {
+        Graph::with_mode(true)
     }
 }
 
@@ -121,35 +151,246 @@ where
     }
 }
 
-impl<V, E, T> Graph<V, E, T>
+impl<V, E, T, N> Graph<V, E, T, N>
 where
     V: GraphVertexTrait,
-    E: GraphEdgeTrait,
+    E: GraphPayloadTrait,
     T: EdgeTypeTrait,
 {
+    fn with_mode(multigraph: bool) -> Self {
+        Graph {
+            adj_list: BTreeMap::new(),
+            in_list: None,
+            edge_ids: multigraph.then(BTreeMap::new),
+            next_edge_id: 0,
+            vertex_data: BTreeMap::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn is_multigraph(&self) -> bool {
+        self.edge_ids.is_some()
+    }
+
+    /// Keeps an index of the incoming edges of every vertex, updated along with the graph,
+    /// so in_edges and in_degree don't need to scan the whole adjacency list.
+    /// Undirected graphs don't need it: their incoming edges are the adjacent ones.
+    pub fn with_in_edges(mut self) -> Self {
+        if T::is_directed() && self.in_list.is_none() {
+            let mut in_list: BTreeMap<V, Vec<(V, E)>> = self
+                .adj_list
+                .keys()
+                .map(|v| (v.clone(), Vec::new()))
+                .collect();
+            for (from, to, value) in self.edges() {
+                in_list.entry(to).or_default().push((from, value));
+            }
+            self.in_list = Some(in_list);
+        }
+        self
+    }
+
+    pub fn has_in_edges_index(&self) -> bool {
+        self.in_list.is_some()
+    }
+
+    /// Starts keeping a payload of type M for the vertices, set through add_vertex_with.
+    /// Payloads the vertices already had are dropped.
+    pub fn with_vertex_data<M>(self) -> Graph<V, E, T, M> {
+        Graph {
+            adj_list: self.adj_list,
+            in_list: self.in_list,
+            edge_ids: self.edge_ids,
+            next_edge_id: self.next_edge_id,
+            vertex_data: BTreeMap::new(),
+            phantom: PhantomData,
+        }
+    }
+
     pub fn add_vertex(&mut self, vertex: V) {
-        self.adj_list.entry(vertex).or_insert(Vec::new());
+        if let Some(in_list) = self.in_list.as_mut() {
+            in_list.entry(vertex.clone()).or_default();
+        }
+        self.adj_list.entry(vertex).or_default();
+    }
+
+    /// Adds the vertex when missing and sets its payload, returning the one it replaces
+    pub fn add_vertex_with(&mut self, vertex: V, data: N) -> Option<N> {
+        self.add_vertex(vertex.clone());
+        self.vertex_data.insert(vertex, data)
+    }
+
+    pub fn vertex_data(&self, vertex: V) -> Option<&N> {
+        self.vertex_data.get(&vertex)
+    }
+
+    pub fn vertex_data_mut(&mut self, vertex: V) -> Option<&mut N> {
+        self.vertex_data.get_mut(&vertex)
     }
 
     // There is no method/function overload in rust, and there is not default parameter
     pub fn add_edge(&mut self, from: V, to: V, value: E) {
-        self.adj_list.entry(to).or_insert(Vec::new());
-        self.adj_list.entry(from).or_insert(Vec::new());
+        self.insert_edge(from, to, value);
+    }
 
-        if self.adj_list.get(&to).is_some() {
-            if let Some(neighbours) = self.adj_list.get_mut(&from) {
-                if !neighbours.contains(&(to, value)) {
-                    neighbours.push((to, value));
-                }
+    /// Adds an edge and returns its id.
+    /// Simple graphs skip an edge identical to one already there and don't hand out ids,
+    /// so for them this always returns None.
+    pub fn insert_edge(&mut self, from: V, to: V, value: E) -> Option<EdgeId> {
+        self.add_vertex(to.clone());
+        self.add_vertex(from.clone());
+
+        let neighbours = self.adj_list.entry(from.clone()).or_default();
+        if self.edge_ids.is_none() && neighbours.iter().any(|(v, e)| *v == to && *e == value) {
+            return None;
+        }
+        neighbours.push((to.clone(), value.clone()));
+        if let Some(in_list) = self.in_list.as_mut() {
+            in_list
+                .entry(to.clone())
+                .or_default()
+                .push((from.clone(), value.clone()));
+        }
+        if !T::is_directed() && from != to {
+            self.adj_list
+                .entry(to.clone())
+                .or_default()
+                .push((from.clone(), value.clone()));
+        }
+
+        let edge_ids = self.edge_ids.as_mut()?;
+        let id = EdgeId(self.next_edge_id);
+        self.next_edge_id += 1;
+        edge_ids.insert(id, (from, to, value));
+        Some(id)
+    }
+
+    /// The (from, to, value) of the edge with the given id
+    pub fn edge(&self, id: EdgeId) -> Option<(V, V, E)> {
+        self.edge_ids.as_ref()?.get(&id).cloned()
+    }
+
+    /// Ids and values of the multigraph edges going from one vertex to the other
+    /// (either way when undirected), oldest first. O(E).
+    pub fn edges_between(&self, from: V, to: V) -> Vec<(EdgeId, E)> {
+        self.edge_ids
+            .iter()
+            .flatten()
+            .filter(|(_, (f, t, _))| joins::<V, T>((f, t), &from, &to))
+            .map(|(id, (_, _, value))| (*id, value.clone()))
+            .collect()
+    }
+
+    /// Removes the edge with the given id, leaving its parallel edges alone.
+    /// Returns its (from, to, value), or None when there is no such edge.
+    pub fn remove_edge_by_id(&mut self, id: EdgeId) -> Option<(V, V, E)> {
+        let (from, to, value) = self.edge_ids.as_mut()?.remove(&id)?;
+        relink_one(self.adj_list.get_mut(&from), (&to, &value), None);
+        if let Some(in_list) = self.in_list.as_mut() {
+            relink_one(in_list.get_mut(&to), (&from, &value), None);
+        }
+        if !T::is_directed() && from != to {
+            relink_one(self.adj_list.get_mut(&to), (&from, &value), None);
+        }
+        Some((from, to, value))
+    }
+
+    /// Sets the value of the edge with the given id, leaving its parallel edges alone.
+    /// Returns false when there is no such edge.
+    pub fn update_edge_by_id(&mut self, id: EdgeId, value: E) -> bool {
+        let (from, to, old) = match self.edge_ids.as_mut().and_then(|ids| ids.get_mut(&id)) {
+            Some(edge) => (
+                edge.0.clone(),
+                edge.1.clone(),
+                std::mem::replace(&mut edge.2, value.clone()),
+            ),
+            None => return false,
+        };
+        relink_one(self.adj_list.get_mut(&from), (&to, &old), Some(&value));
+        if let Some(in_list) = self.in_list.as_mut() {
+            relink_one(in_list.get_mut(&to), (&from, &old), Some(&value));
+        }
+        if !T::is_directed() && from != to {
+            relink_one(self.adj_list.get_mut(&to), (&from, &old), Some(&value));
+        }
+        true
+    }
+
+    /// Removes the vertex along with every edge touching it.
+    /// Returns false when the vertex wasn't in the graph.
+    pub fn remove_vertex(&mut self, vertex: V) -> bool {
+        let links = match self.adj_list.remove(&vertex) {
+            Some(links) => links,
+            None => return false,
+        };
+
+        if !T::is_directed() {
+            // Only the neighbours hold the mirrored edges
+            for (to, _) in links {
+                unlink(self.adj_list.get_mut(&to), &vertex);
+            }
+        } else if let Some(in_list) = self.in_list.as_mut() {
+            for (from, _) in in_list.remove(&vertex).unwrap_or_default() {
+                unlink(self.adj_list.get_mut(&from), &vertex);
+            }
+            for (to, _) in links {
+                unlink(in_list.get_mut(&to), &vertex);
+            }
+        } else {
+            for neighbours in self.adj_list.values_mut() {
+                unlink(Some(neighbours), &vertex);
             }
         }
-        if !T::is_directed() && self.adj_list.get(&from).is_some() {
-            if let Some(neighbours) = self.adj_list.get_mut(&to) {
-                if !neighbours.contains(&(from, value)) {
-                    neighbours.push((from, value));
+        if let Some(edge_ids) = self.edge_ids.as_mut() {
+            edge_ids.retain(|_, (from, to, _)| *from != vertex && *to != vertex);
+        }
+        self.vertex_data.remove(&vertex);
+        true
+    }
+
+    /// Removes the edges going from one vertex to the other (both directions when undirected),
+    /// including parallel ones. Returns false when there was no such edge.
+    pub fn remove_edge(&mut self, from: V, to: V) -> bool {
+        if unlink(self.adj_list.get_mut(&from), &to) == 0 {
+            return false;
+        }
+        if let Some(in_list) = self.in_list.as_mut() {
+            unlink(in_list.get_mut(&to), &from);
+        }
+        if !T::is_directed() {
+            unlink(self.adj_list.get_mut(&to), &from);
+        }
+        if let Some(edge_ids) = self.edge_ids.as_mut() {
+            edge_ids.retain(|_, (f, t, _)| !joins::<V, T>((f, t), &from, &to));
+        }
+        true
+    }
+
+    /// Sets the value of the edge going from one vertex to the other (both directions when undirected).
+    /// Parallel edges between them collapse into this single one.
+    /// Returns false when there was no such edge.
+    pub fn update_edge_weight(&mut self, from: V, to: V, value: E) -> bool {
+        if !relink(self.adj_list.get_mut(&from), &to, &value) {
+            return false;
+        }
+        if let Some(in_list) = self.in_list.as_mut() {
+            relink(in_list.get_mut(&to), &from, &value);
+        }
+        if !T::is_directed() {
+            relink(self.adj_list.get_mut(&to), &from, &value);
+        }
+        if let Some(edge_ids) = self.edge_ids.as_mut() {
+            // The oldest of the parallel edges is the one left
+            let mut kept = false;
+            edge_ids.retain(|_, (f, t, v)| {
+                if !joins::<V, T>((f, t), &from, &to) {
+                    return true;
                 }
-            }
+                *v = value.clone();
+                !std::mem::replace(&mut kept, true)
+            });
         }
+        true
     }
 
     pub fn get_adjacent_vertices(&self, v: V) -> Option<&Vec<(V, E)>> {
@@ -157,15 +398,50 @@ where
     }
 
     pub fn get_incoming_vertices(&self, v: V) -> Option<Vec<V>> {
-        let mut incoming: Vec<V> = Vec::new();        
-        for from in &self.adj_list {
-            for to in from.1 {
-                if to.0 == v {
-                    incoming.push(*from.0);
-                }
-            }
-        }
-        Some(incoming)
+        Some(self.in_edges(v).map(|(from, _)| from).collect())
+    }
+
+    /// Edges arriving at v, as (from, value) pairs, in no particular order.
+    /// O(deg) for undirected graphs or when the in-edges index is kept, O(V + E) otherwise.
+    pub fn in_edges(&self, v: V) -> impl Iterator<Item = (V, E)> + '_ {
+        self.in_links(v)
+            .map(|(from, value)| (from.clone(), value.clone()))
+    }
+
+    /// Borrowed in-edges of the vertex, taken by value or by reference.
+    /// Undirected graphs are their own reverse, directed ones use the in-edges index when kept.
+    fn in_links<'a, K>(&'a self, vertex: K) -> impl Iterator<Item = (&'a V, &'a E)>
+    where
+        K: Borrow<V> + Clone + 'a,
+    {
+        let listed = match (&self.in_list, T::is_directed()) {
+            (_, false) => Some(self.adj_list.get(vertex.borrow())),
+            (Some(in_list), true) => Some(in_list.get(vertex.borrow())),
+            (None, true) => None,
+        };
+        let scanned = listed.is_none().then(|| {
+            self.adj_list.iter().flat_map(move |(from, links)| {
+                let vertex = vertex.clone();
+                links
+                    .iter()
+                    .filter(move |(to, _)| to == vertex.borrow())
+                    .map(move |(_, value)| (from, value))
+            })
+        });
+        listed
+            .flatten()
+            .into_iter()
+            .flatten()
+            .map(|(from, value)| (from, value))
+            .chain(scanned.into_iter().flatten())
+    }
+
+    pub fn in_degree(&self, v: V) -> usize {
+        self.in_edges(v).count()
+    }
+
+    pub fn out_degree(&self, v: V) -> usize {
+        self.adj_list.get(&v).map_or(0, |links| links.len())
     }
 
     pub fn adj_list(&self) -> &BTreeMap<V, Vec<(V, E)>> {
@@ -188,17 +464,29 @@ where
         let mut edges: Vec<(V, V, E)> = Vec::new();
         for from in &self.adj_list {
             for to in from.1 {
-                edges.push((*from.0, to.0, to.1));
+                edges.push((from.0.clone(), to.0.clone(), to.1.clone()));
             }
         }
         edges
     }
 
+    /// Immutable copy of the graph in compressed sparse row form, for read-heavy workloads
+    pub fn freeze(&self) -> CsrGraph<V, E, T> {
+        CsrGraph::from(self)
+    }
+}
+
+impl<V, E, T, N> Graph<V, E, T, N>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    T: EdgeTypeTrait,
+{
     pub fn edges_with_weights(&self, order: std::cmp::Ordering) -> Vec<(V, V, E)> {
         let mut edges: Vec<(V, V, E)> = Vec::new();
         for from in &self.adj_list {
             for to in from.1 {
-                edges.push((*from.0, to.0, to.1));
+                edges.push((from.0.clone(), to.0.clone(), to.1));
             }
         }
 
@@ -221,6 +509,102 @@ where
     }
 }
 
+impl<V, E, T, N> GraphStorage for Graph<V, E, T, N>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    type Vertex = V;
+    type Edge = E;
+    type EdgeType = T;
+
+    fn vertices_count(&self) -> usize {
+        self.adj_list.len()
+    }
+
+    fn contains_vertex(&self, vertex: &V) -> bool {
+        self.adj_list.contains_key(vertex)
+    }
+
+    fn iter_vertices(&self) -> impl Iterator<Item = &V> {
+        self.adj_list.keys()
+    }
+
+    fn neighbours(&self, vertex: &V) -> impl Iterator<Item = (&V, &E)> {
+        self.adj_list
+            .get(vertex)
+            .into_iter()
+            .flatten()
+            .map(|(to, value)| (to, value))
+    }
+
+    fn incoming<'a>(&'a self, vertex: &'a V) -> impl Iterator<Item = (&'a V, &'a E)> {
+        self.in_links(vertex)
+    }
+}
+
+/// Drops the links to target from an adjacency list, returning how many there were
+fn unlink<V: GraphVertexTrait, E: GraphPayloadTrait>(
+    links: Option<&mut Vec<(V, E)>>,
+    target: &V,
+) -> usize {
+    match links {
+        Some(links) => {
+            let before = links.len();
+            links.retain(|(v, _)| v != target);
+            before - links.len()
+        }
+        None => 0,
+    }
+}
+
+/// Keeps a single link to target in an adjacency list, with the given value
+fn relink<V: GraphVertexTrait, E: GraphPayloadTrait>(
+    links: Option<&mut Vec<(V, E)>>,
+    target: &V,
+    value: &E,
+) -> bool {
+    let links = match links {
+        Some(links) => links,
+        None => return false,
+    };
+    let first = match links.iter().position(|(v, _)| v == target) {
+        Some(first) => first,
+        None => return false,
+    };
+    links[first].1 = value.clone();
+    let mut i = 0;
+    links.retain(|(v, _)| {
+        i += 1;
+        i - 1 == first || v != target
+    });
+    true
+}
+
+/// Changes one link equal to the given one to the new value, or drops it when there is none
+fn relink_one<V: GraphVertexTrait, E: GraphPayloadTrait>(
+    links: Option<&mut Vec<(V, E)>>,
+    link: (&V, &E),
+    value: Option<&E>,
+) {
+    if let Some(links) = links {
+        if let Some(i) = links.iter().position(|(v, e)| v == link.0 && e == link.1) {
+            match value {
+                Some(value) => links[i].1 = value.clone(),
+                None => {
+                    links.remove(i);
+                }
+            }
+        }
+    }
+}
+
+/// Whether an edge's endpoints go from one vertex to the other, in either order when undirected
+fn joins<V: GraphVertexTrait, T: EdgeTypeTrait>(edge: (&V, &V), from: &V, to: &V) -> bool {
+    edge == (from, to) || (!T::is_directed() && edge == (to, from))
+}
+
 #[cfg(test)]
 mod test_graph {
     use super::Graph;
@@ -332,4 +716,292 @@ mod test_graph {
             vec![("TOR", "NYC", 2), ("NYC", "MTL", 530), ("MTL", "TOR", 590)]
         );
     }
+
+    #[test]
+    fn test_in_edges() {
+        let mut plain = Graph::new();
+        let mut indexed = Graph::new().with_in_edges();
+        for g in [&mut plain, &mut indexed] {
+            g.add_edge("NYC", "MTL", 530);
+            g.add_edge("NYC", "TOR", 560);
+            g.add_edge("MTL", "TOR", 525);
+            g.add_edge("MTL", "TOR", 525);
+            g.add_vertex("BOS");
+        }
+        assert!(!plain.has_in_edges_index());
+        assert!(indexed.has_in_edges_index());
+        assert_eq!(plain, indexed);
+
+        for g in [&plain, &indexed] {
+            let mut tor = g.in_edges("TOR").collect::<Vec<_>>();
+            tor.sort();
+            assert_eq!(vec![("MTL", 525), ("NYC", 560)], tor);
+            assert_eq!(Some(vec!["NYC"]), g.get_incoming_vertices("MTL"));
+            assert_eq!(2, g.in_degree("TOR"));
+            assert_eq!(0, g.in_degree("NYC"));
+            assert_eq!(2, g.out_degree("NYC"));
+            assert_eq!(0, g.in_degree("BOS"));
+            assert_eq!(0, g.out_degree("SEA"));
+        }
+
+        // Indexing an existing graph picks up the edges already there
+        let late = plain.with_in_edges();
+        assert_eq!(2, late.in_degree("TOR"));
+    }
+
+    #[test]
+    fn test_in_edges_undirected() {
+        let mut g = Graph::new_undirected().with_in_edges();
+        g.add_edge(1, 2, 7);
+        g.add_edge(1, 3, 8);
+        assert!(!g.has_in_edges_index());
+        assert_eq!(vec![(2, 7), (3, 8)], g.in_edges(1).collect::<Vec<_>>());
+        assert_eq!(2, g.in_degree(1));
+        assert_eq!(2, g.out_degree(1));
+        assert_eq!(1, g.in_degree(3));
+    }
+
+    #[test]
+    fn test_remove_vertex() {
+        let mut plain = Graph::new();
+        let mut indexed = Graph::new().with_in_edges();
+        for g in [&mut plain, &mut indexed] {
+            g.add_edge("NYC", "MTL", 530);
+            g.add_edge("NYC", "TOR", 560);
+            g.add_edge("MTL", "TOR", 525);
+            g.add_edge("TOR", "NYC", 560);
+            g.add_edge("TOR", "TOR", 1);
+
+            assert!(g.remove_vertex("TOR"));
+            assert!(!g.remove_vertex("TOR"));
+            assert!(!g.contains("TOR"));
+            assert_eq!(vec![("NYC", "MTL", 530)], g.edges());
+            assert_eq!(0, g.in_degree("NYC"));
+            assert_eq!(1, g.in_degree("MTL"));
+        }
+        assert_eq!(plain, indexed);
+
+        let mut g = Graph::new_undirected();
+        g.add_edge(1, 2, 5);
+        g.add_edge(2, 3, 6);
+        g.add_edge(3, 1, 7);
+        assert!(g.remove_vertex(3));
+        assert_eq!(vec![(1, 2, 5), (2, 1, 5)], g.edges());
+        assert_eq!(2, g.vertices_count());
+    }
+
+    #[test]
+    fn test_remove_edge() {
+        let mut g = Graph::new().with_in_edges();
+        g.add_edge(1, 2, 5);
+        g.add_edge(1, 2, 6);
+        g.add_edge(2, 1, 7);
+        assert!(g.remove_edge(1, 2));
+        assert!(!g.remove_edge(1, 2));
+        assert!(!g.remove_edge(1, 9));
+        assert_eq!(vec![(2, 1, 7)], g.edges());
+        assert_eq!(0, g.in_degree(2));
+        assert!(g.contains(2));
+
+        let mut g = Graph::new_undirected();
+        g.add_edge("A", "B", 1);
+        g.add_edge("B", "C", 2);
+        assert!(g.remove_edge("B", "A"));
+        assert_eq!(vec![("B", "C", 2), ("C", "B", 2)], g.edges());
+        assert_eq!(0, g.out_degree("A"));
+    }
+
+    #[test]
+    fn test_update_edge_weight() {
+        let mut g = Graph::new().with_in_edges();
+        g.add_edge(1, 2, 5);
+        g.add_edge(1, 3, 4);
+        g.add_edge(1, 2, 6);
+        assert!(g.update_edge_weight(1, 2, 9));
+        assert!(!g.update_edge_weight(2, 1, 9));
+        assert_eq!(vec![(1, 2, 9), (1, 3, 4)], g.edges());
+        assert_eq!(vec![(1, 9)], g.in_edges(2).collect::<Vec<_>>());
+
+        let mut g = Graph::new_undirected();
+        g.add_edge("A", "B", 1);
+        g.add_edge("B", "C", 2);
+        assert!(g.update_edge_weight("C", "B", 3));
+        assert_eq!(
+            g.edges_with_weights(Ordering::Less),
+            vec![("A", "B", 1), ("B", "A", 1), ("B", "C", 3), ("C", "B", 3)]
+        );
+    }
+
+    #[test]
+    fn test_multigraph_parallel_edges() {
+        let mut g = Graph::new_multigraph().with_in_edges();
+        assert!(g.is_multigraph());
+        let ferry = g.insert_edge("NYC", "BOS", 4).unwrap();
+        let train = g.insert_edge("NYC", "BOS", 4).unwrap();
+        let bus = g.insert_edge("NYC", "BOS", 6).unwrap();
+        assert_ne!(ferry, train);
+        assert_eq!(3, g.edges().len());
+        assert_eq!(3, g.in_degree("BOS"));
+        assert_eq!(Some(("NYC", "BOS", 4)), g.edge(train));
+        assert_eq!(
+            vec![(ferry, 4), (train, 4), (bus, 6)],
+            g.edges_between("NYC", "BOS")
+        );
+        assert!(g.edges_between("BOS", "NYC").is_empty());
+
+        assert!(g.update_edge_by_id(train, 3));
+        assert_eq!(Some(("NYC", "BOS", 3)), g.edge(train));
+        assert_eq!(Some(("NYC", "BOS", 4)), g.edge(ferry));
+        assert_eq!(Some(("NYC", "BOS", 3)), g.remove_edge_by_id(train));
+        assert_eq!(None, g.remove_edge_by_id(train));
+        assert!(!g.update_edge_by_id(train, 1));
+        assert_eq!(vec![("NYC", "BOS", 4), ("NYC", "BOS", 6)], g.edges());
+        let mut bos = g.in_edges("BOS").collect::<Vec<_>>();
+        bos.sort();
+        assert_eq!(vec![("NYC", 4), ("NYC", 6)], bos);
+
+        // Ids stay put while the other edges change
+        let plane = g.insert_edge("NYC", "BOS", 1).unwrap();
+        assert_eq!(Some(("NYC", "BOS", 6)), g.edge(bus));
+        assert!(g.update_edge_weight("NYC", "BOS", 2));
+        assert_eq!(vec![(ferry, 2)], g.edges_between("NYC", "BOS"));
+        assert_eq!(None, g.edge(plane));
+        assert!(g.remove_vertex("BOS"));
+        assert_eq!(None, g.edge(ferry));
+    }
+
+    #[test]
+    fn test_multigraph_undirected() {
+        let mut g = Graph::new_undirected_multigraph();
+        let a = g.insert_edge(1, 2, 5).unwrap();
+        let b = g.insert_edge(2, 1, 5).unwrap();
+        g.insert_edge(2, 2, 1);
+        assert_eq!(5, g.edges().len());
+        assert_eq!(vec![(a, 5), (b, 5)], g.edges_between(2, 1));
+
+        assert!(g.update_edge_by_id(b, 7));
+        assert_eq!(
+            vec![(1, 2, 5), (1, 2, 7), (2, 1, 5), (2, 1, 7), (2, 2, 1)],
+            {
+                let mut edges = g.edges();
+                edges.sort();
+                edges
+            }
+        );
+        assert_eq!(Some((1, 2, 5)), g.remove_edge_by_id(a));
+        assert_eq!(vec![(1, 2, 7), (2, 1, 7), (2, 2, 1)], {
+            let mut edges = g.edges();
+            edges.sort();
+            edges
+        });
+        assert!(g.remove_edge(1, 2));
+        assert_eq!(None, g.edge(b));
+    }
+
+    #[test]
+    fn test_simple_graph_has_no_edge_ids() {
+        let mut g = Graph::new();
+        assert!(!g.is_multigraph());
+        assert_eq!(None, g.insert_edge(1, 2, 3));
+        assert_eq!(None, g.insert_edge(1, 2, 3));
+        assert!(g.edges_between(1, 2).is_empty());
+        assert_eq!(1, g.edges().len());
+    }
+
+    #[test]
+    fn test_string_vertices() {
+        let mut g: Graph<String, u32> = Graph::new().with_in_edges();
+        for (from, to, value) in [
+            ("NYC", "MTL", 530),
+            ("NYC", "TOR", 560),
+            ("MTL", "TOR", 525),
+        ] {
+            g.add_edge(from.to_string(), to.to_string(), value);
+        }
+        assert!(g.contains("TOR".to_string()));
+        assert_eq!(2, g.in_degree("TOR".to_string()));
+        assert!(g.update_edge_weight("NYC".to_string(), "TOR".to_string(), 550));
+        assert!(g.remove_vertex("MTL".to_string()));
+        assert_eq!(vec![("NYC".to_string(), "TOR".to_string(), 550)], g.edges());
+    }
+
+    #[test]
+    fn test_vertex_and_edge_payloads() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Link {
+            line: &'static str,
+            minutes: u32,
+        }
+
+        let mut g = Graph::new_undirected().with_vertex_data::<(f64, f64)>();
+        assert_eq!(None, g.add_vertex_with("Lisbon", (38.7, -9.1)));
+        g.add_vertex_with("Porto", (41.1, -8.6));
+        g.add_edge(
+            "Lisbon",
+            "Porto",
+            Link {
+                line: "AP",
+                minutes: 170,
+            },
+        );
+        g.add_edge(
+            "Lisbon",
+            "Porto",
+            Link {
+                line: "IC",
+                minutes: 195,
+            },
+        );
+        g.add_edge(
+            "Porto",
+            "Braga",
+            Link {
+                line: "U",
+                minutes: 55,
+            },
+        );
+
+        assert_eq!(Some(&(41.1, -8.6)), g.vertex_data("Porto"));
+        assert_eq!(None, g.vertex_data("Braga"));
+        g.vertex_data_mut("Lisbon").unwrap().0 = 38.72;
+        assert_eq!(Some((38.72, -9.1)), g.add_vertex_with("Lisbon", (0.0, 0.0)));
+        assert_eq!(2, g.out_degree("Lisbon"));
+        assert_eq!("U", g.get_adjacent_vertices("Braga").unwrap()[0].1.line);
+
+        assert!(g.remove_vertex("Porto"));
+        assert_eq!(None, g.vertex_data("Porto"));
+        assert!(g.edges().is_empty());
+    }
+
+    #[test]
+    fn test_payload_storage_conversions() {
+        use crate::{dijkstra_by, EdgeListGraph, GraphStorage, MatrixGraph};
+
+        #[derive(Debug, Clone, PartialEq)]
+        struct Link {
+            line: &'static str,
+            minutes: u32,
+        }
+        let link = |line, minutes| Link { line, minutes };
+
+        let mut g = Graph::new();
+        g.add_edge("Lisbon", "Porto", link("IC", 195));
+        g.add_edge("Lisbon", "Porto", link("AP", 170));
+        g.add_edge("Porto", "Braga", link("U", 55));
+        let minutes = |link: &Link| link.minutes;
+
+        let frozen = g.freeze();
+        let list = EdgeListGraph::from(&g);
+        assert_eq!(3, frozen.edges_count());
+        assert_eq!(3, list.edges_count());
+        let expected = dijkstra_by(&g, "Lisbon", minutes).unwrap();
+        assert_eq!(expected, dijkstra_by(&frozen, "Lisbon", minutes).unwrap());
+        assert_eq!(expected, dijkstra_by(&list, "Lisbon", minutes).unwrap());
+
+        // A cell holds one edge, the fastest of the parallel ones
+        let matrix = MatrixGraph::from_graph_by(&g, minutes);
+        assert_eq!(2, matrix.edges_count());
+        assert_eq!(Some(link("AP", 170)), matrix.edge("Lisbon", "Porto"));
+        assert_eq!(expected, dijkstra_by(&matrix, "Lisbon", minutes).unwrap());
+    }
 }
@@ -1,6 +1,31 @@
+// The original tests compare booleans with assert_eq! and use the old numeric constants
+#![cfg_attr(
+    test,
+    allow(clippy::bool_assert_comparison, clippy::legacy_numeric_constants)
+)]
+
 mod graph;
 pub use graph::*;
 
+mod storage;
+pub use storage::GraphStorage;
+
+mod matrix;
+pub use matrix::MatrixGraph;
+
+mod edge_list;
+pub use edge_list::EdgeListGraph;
+
+mod csr;
+pub use csr::CsrGraph;
+
+mod ordered_float;
+pub use ordered_float::OrderedFloat;
+
+mod error;
+pub use error::GraphError;
+pub use error::NegativeCycle;
+
 mod search;
 pub use search::breadth_first_search;
 pub use search::depth_first_search;
@@ -10,17 +35,57 @@ pub use sort::topological_sort;
 
 mod mst;
 pub use mst::kruskal;
+pub use mst::kruskal_by;
+pub use mst::kruskal_with;
 pub use mst::prim;
+pub use mst::prim_by;
+pub use mst::prim_with;
+pub use mst::SpanningTree;
 
 mod single_path;
+pub use single_path::astar;
 pub use single_path::bellman_ford;
+pub use single_path::bellman_ford_by;
+pub use single_path::bellman_ford_with;
+pub use single_path::bidirectional_dijkstra;
 pub use single_path::dijkstra;
+pub use single_path::dijkstra_by;
+pub use single_path::dijkstra_with;
+pub use single_path::k_shortest_paths;
+pub use single_path::shortest_path;
+pub use single_path::shortest_paths;
+pub use single_path::Path;
+pub use single_path::ShortestPaths;
 
 mod all_path;
 pub use all_path::floyd_warshall;
+pub use all_path::floyd_warshall_by;
+pub use all_path::floyd_warshall_with;
+pub use all_path::johnson;
+pub use all_path::johnson_by;
+pub use all_path::johnson_with;
+pub use all_path::AllPairsShortestPaths;
 
 mod max_flow;
+pub use max_flow::dinic;
 pub use max_flow::edmonds_karp;
+pub use max_flow::edmonds_karp_observed;
+pub use max_flow::max_flow;
+pub use max_flow::min_cost_flow;
+pub use max_flow::min_cost_max_flow;
+pub use max_flow::push_relabel;
+pub use max_flow::FlowObserver;
+pub use max_flow::MaxFlow;
+pub use max_flow::MinCostFlow;
+pub use max_flow::ResidualPrinter;
 
 mod pagerank;
 pub use pagerank::pagerank;
+pub use pagerank::personalized_pagerank;
+pub use pagerank::PageRank;
+
+mod centrality;
+pub use centrality::betweenness_centrality;
+pub use centrality::closeness_centrality;
+pub use centrality::degree_centrality;
+pub use centrality::eigenvector_centrality;
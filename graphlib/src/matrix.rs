@@ -0,0 +1,307 @@
+use super::{
+    Directed, EdgeTypeTrait, Graph, GraphEdgeTrait, GraphPayloadTrait, GraphStorage,
+    GraphVertexTrait, Undirected,
+};
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+
+// Adjacency matrix representation: constant time edge lookups and no tree per vertex,
+// at the price of |V|x|V| memory. Meant for dense graphs.
+
+/// Graph stored as a |V|x|V| matrix of optional edge values.
+/// Holds at most one edge per (from, to) pair, adding it again replaces its value.
+#[derive(Debug, Default)]
+pub struct MatrixGraph<V: GraphVertexTrait, E: GraphPayloadTrait, T = Directed> {
+    // Vertex of each row/column, in insertion order
+    keys: Vec<V>,
+    index: BTreeMap<V, usize>,
+    matrix: Vec<Vec<Option<E>>>,
+    phantom: PhantomData<T>,
+}
+
+impl<V, E> MatrixGraph<V, E, Directed>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+{
+    pub fn new() -> Self {
+        MatrixGraph::empty()
+    }
+}
+
+impl<E> MatrixGraph<usize, E, Directed>
+where
+    E: GraphEdgeTrait,
+{
+    /// Reads a square matrix such as the ones edmonds_karp takes, vertex i being row i.
+    /// Zero (the default value) means there is no edge.
+    pub fn from_adjacency_matrix(matrix: &[Vec<E>]) -> Self {
+        let mut graph = MatrixGraph::new();
+        for (from, row) in matrix.iter().enumerate() {
+            graph.add_vertex(from);
+            for (to, value) in row.iter().enumerate() {
+                if *value != E::default() {
+                    graph.add_edge(from, to, *value);
+                }
+            }
+        }
+        graph
+    }
+}
+
+impl<V, E> MatrixGraph<V, E, Undirected>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+{
+    pub fn new_undirected() -> Self {
+        MatrixGraph::empty()
+    }
+}
+
+impl<V, E, T> MatrixGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    fn empty() -> Self {
+        MatrixGraph {
+            keys: Vec::new(),
+            index: BTreeMap::new(),
+            matrix: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Adds a row and a column for the vertex, O(V)
+    pub fn add_vertex(&mut self, vertex: V) {
+        if self.index.contains_key(&vertex) {
+            return;
+        }
+        self.index.insert(vertex.clone(), self.keys.len());
+        self.keys.push(vertex);
+        for row in self.matrix.iter_mut() {
+            row.push(None);
+        }
+        self.matrix.push(vec![None; self.keys.len()]);
+    }
+
+    /// Sets the edge from one vertex to the other (both ways when undirected)
+    pub fn add_edge(&mut self, from: V, to: V, value: E) {
+        self.add_vertex(from.clone());
+        self.add_vertex(to.clone());
+        let (i, j) = (self.index[&from], self.index[&to]);
+        if !T::is_directed() {
+            self.matrix[j][i] = Some(value.clone());
+        }
+        self.matrix[i][j] = Some(value);
+    }
+
+    /// Returns false when there was no such edge
+    pub fn remove_edge(&mut self, from: V, to: V) -> bool {
+        let (i, j) = match (self.index.get(&from), self.index.get(&to)) {
+            (Some(i), Some(j)) => (*i, *j),
+            _ => return false,
+        };
+        if !T::is_directed() {
+            self.matrix[j][i] = None;
+        }
+        self.matrix[i][j].take().is_some()
+    }
+
+    pub fn edge(&self, from: V, to: V) -> Option<E> {
+        self.matrix[*self.index.get(&from)?][*self.index.get(&to)?].clone()
+    }
+
+    pub fn contains(&self, vertex: V) -> bool {
+        self.index.contains_key(&vertex)
+    }
+}
+
+impl<V, E, T> MatrixGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    /// Copies a graph whose edges carry any payload. Parallel edges can't share a cell,
+    /// so only the lightest of them is kept, weighed by the closure
+    pub fn from_graph_by<N, W, F>(graph: &Graph<V, E, T, N>, weight: F) -> Self
+    where
+        W: Ord,
+        F: Fn(&E) -> W,
+    {
+        let mut matrix = MatrixGraph::empty();
+        for vertex in graph.iter_vertices() {
+            matrix.add_vertex(vertex.clone());
+        }
+        for (from, to, value) in graph.iter_edges() {
+            let (i, j) = (matrix.index[from], matrix.index[to]);
+            if matrix.matrix[i][j]
+                .as_ref()
+                .is_none_or(|current| weight(value) < weight(current))
+            {
+                matrix.add_edge(from.clone(), to.clone(), value.clone());
+            }
+        }
+        matrix
+    }
+}
+
+/// Parallel edges can't share a cell, only the lightest of them is kept.
+/// Graphs with other payloads on their edges go through `MatrixGraph::from_graph_by`
+impl<V, E, T, N> From<&Graph<V, E, T, N>> for MatrixGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    T: EdgeTypeTrait,
+{
+    fn from(graph: &Graph<V, E, T, N>) -> Self {
+        MatrixGraph::from_graph_by(graph, |value| *value)
+    }
+}
+
+impl<V, E, T> GraphStorage for MatrixGraph<V, E, T>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    T: EdgeTypeTrait,
+{
+    type Vertex = V;
+    type Edge = E;
+    type EdgeType = T;
+
+    fn vertices_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn contains_vertex(&self, vertex: &V) -> bool {
+        self.index.contains_key(vertex)
+    }
+
+    fn iter_vertices(&self) -> impl Iterator<Item = &V> {
+        self.index.keys()
+    }
+
+    fn neighbours(&self, vertex: &V) -> impl Iterator<Item = (&V, &E)> {
+        self.index
+            .get(vertex)
+            .into_iter()
+            .flat_map(move |i| self.matrix[*i].iter().enumerate())
+            .filter_map(move |(j, value)| value.as_ref().map(|value| (&self.keys[j], value)))
+    }
+
+    fn incoming<'a>(&'a self, vertex: &'a V) -> impl Iterator<Item = (&'a V, &'a E)> {
+        self.index.get(vertex).into_iter().flat_map(move |j| {
+            self.matrix
+                .iter()
+                .enumerate()
+                .filter_map(move |(i, row)| row[*j].as_ref().map(|value| (&self.keys[i], value)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_matrix {
+    use super::MatrixGraph;
+    use crate::{
+        bidirectional_dijkstra, dijkstra, floyd_warshall, max_flow, prim, Graph, GraphStorage,
+    };
+
+    fn cities() -> Graph<&'static str, u32> {
+        let mut graph = Graph::new();
+        graph.add_edge("NYC", "MTL", 530);
+        graph.add_edge("NYC", "TOR", 560);
+        graph.add_edge("NYC", "TOR", 600);
+        graph.add_edge("MTL", "TOR", 525);
+        graph.add_edge("TOR", "NYC", 10);
+        graph.add_vertex("SEA");
+        graph
+    }
+
+    #[test]
+    fn test_matrix_edges() {
+        let mut matrix = MatrixGraph::new();
+        matrix.add_edge(2, 1, 7);
+        matrix.add_edge(1, 3, 8);
+        matrix.add_edge(1, 3, 9);
+        assert_eq!(3, matrix.vertices_count());
+        assert_eq!(Some(9), matrix.edge(1, 3));
+        assert_eq!(None, matrix.edge(3, 1));
+        assert_eq!(vec![&1, &2, &3], matrix.iter_vertices().collect::<Vec<_>>());
+        assert_eq!(
+            vec![(&1, &3, &9), (&2, &1, &7)],
+            matrix.iter_edges().collect::<Vec<_>>()
+        );
+        assert!(matrix.remove_edge(1, 3));
+        assert!(!matrix.remove_edge(1, 3));
+        assert_eq!(1, matrix.edges_count());
+
+        let mut matrix = MatrixGraph::new_undirected();
+        matrix.add_edge("A", "B", 1);
+        assert_eq!(Some(1), matrix.edge("B", "A"));
+        assert_eq!(2, matrix.edges_count());
+        assert!(matrix.remove_edge("B", "A"));
+        assert_eq!(0, matrix.edges_count());
+        assert!(matrix.contains("A"));
+    }
+
+    #[test]
+    fn test_matrix_from_graph() {
+        let graph = cities();
+        let matrix = MatrixGraph::from(&graph);
+        assert_eq!(4, matrix.vertices_count());
+        assert_eq!(Some(560), matrix.edge("NYC", "TOR"));
+        assert_eq!(4, matrix.edges_count());
+    }
+
+    #[test]
+    fn test_matrix_algorithms() {
+        let graph = cities();
+        let matrix = MatrixGraph::from(&graph);
+        assert_eq!(
+            dijkstra(&graph, "NYC").unwrap().distances(),
+            dijkstra(&matrix, "NYC").unwrap().distances()
+        );
+        assert_eq!(
+            floyd_warshall(&graph).unwrap().distances(),
+            floyd_warshall(&matrix).unwrap().distances()
+        );
+        assert_eq!(
+            vec![(&"MTL", &525), (&"NYC", &560)],
+            matrix.incoming(&"TOR").collect::<Vec<_>>()
+        );
+        assert_eq!(
+            bidirectional_dijkstra(&graph, "MTL", "NYC"),
+            bidirectional_dijkstra(&matrix, "MTL", "NYC")
+        );
+
+        let mut undirected = Graph::new_undirected();
+        undirected.add_edge("A", "B", 2);
+        undirected.add_edge("B", "C", 3);
+        undirected.add_edge("A", "C", 4);
+        let (weight, _) = prim(&MatrixGraph::from(&undirected), "A").unwrap();
+        assert_eq!(prim(&undirected, "A").unwrap().0, weight);
+        assert_eq!(5, weight);
+    }
+
+    #[test]
+    fn test_matrix_max_flow() {
+        // Cormen's example, as edmonds_karp takes it
+        let matrix = MatrixGraph::from_adjacency_matrix(&[
+            vec![0, 16, 13, 0, 0, 0],
+            vec![0, 0, 10, 12, 0, 0],
+            vec![0, 4, 0, 0, 14, 0],
+            vec![0, 0, 9, 0, 0, 20],
+            vec![0, 0, 0, 7, 0, 4],
+            vec![0, 0, 0, 0, 0, 0],
+        ]);
+        let flow = max_flow(&matrix, 0, 5).unwrap();
+        assert_eq!(23, flow.value());
+        assert_eq!(
+            vec![0, 1, 2, 4],
+            flow.min_cut().iter().copied().collect::<Vec<_>>()
+        );
+    }
+}
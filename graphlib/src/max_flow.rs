@@ -1,61 +1,126 @@
-use std::collections::{VecDeque};
+use super::{Directed, GraphEdgeTrait, GraphError, GraphStorage, GraphVertexTrait, NegativeCycle};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque};
+use std::ops::{Mul, Sub};
 
-/// Max flow algorithms.
+// Max flow algorithms.
+
+/// Hooks into the progress of edmonds_karp, mostly for teaching and debugging.
+/// Every method does nothing by default, so an observer only implements the events it cares about.
+pub trait FlowObserver {
+    /// Called once, before the first augmenting path is searched
+    fn started(&mut self, _residual_graph: &[Vec<i32>]) {}
+    /// Called for each augmenting path, given from source to sink, with the flow pushed through it
+    fn augmenting_path(&mut self, _path: &[usize], _path_flow: i32) {}
+    /// Called after the residual capacities were updated with the last augmenting path
+    fn residual_updated(&mut self, _residual_graph: &[Vec<i32>]) {}
+    /// Called once no augmenting path is left
+    fn finished(&mut self, _max_flow: i32, _residual_graph: &[Vec<i32>]) {}
+}
+
+/// The silent observer
+impl FlowObserver for () {}
+
+/// Observer that prints the residual graph at every step, as the algorithm is taught in class
+#[derive(Debug, Default)]
+pub struct ResidualPrinter;
+
+impl ResidualPrinter {
+    fn print(residual_graph: &[Vec<i32>]) {
+        for row in residual_graph {
+            println!("{:?}", row);
+        }
+    }
+}
+
+impl FlowObserver for ResidualPrinter {
+    fn started(&mut self, residual_graph: &[Vec<i32>]) {
+        println!("Grafo inicial:");
+        Self::print(residual_graph);
+    }
+
+    fn residual_updated(&mut self, residual_graph: &[Vec<i32>]) {
+        // Mostra as capacidades restantes de cada aresta - normal e reversa
+        println!("Grafo residual a cada iteração:");
+        Self::print(residual_graph);
+    }
+
+    fn finished(&mut self, max_flow: i32, residual_graph: &[Vec<i32>]) {
+        println!();
+        println!("Grafo residual final:");
+        Self::print(residual_graph);
+        println!("Fluxo máximo: {}", max_flow);
+    }
+}
 
 // Implementação do algoritmo de Edmonds-Karp para encontrar o fluxo máximo em um grafo
-pub fn edmonds_karp(graph: &Vec<Vec<i32>>, source: usize, sink: usize) -> i32 {
+// Falha com MissingVertex quando source ou sink não é uma linha da matriz, e com SameSourceAndSink
+pub fn edmonds_karp(
+    graph: &[Vec<i32>],
+    source: usize,
+    sink: usize,
+) -> Result<i32, GraphError<usize, i32>> {
+    edmonds_karp_observed(graph, source, sink, &mut ())
+}
+
+// Mesmo algoritmo, avisando o observer a cada passo (use ResidualPrinter para imprimir o grafo residual)
+pub fn edmonds_karp_observed<O: FlowObserver>(
+    graph: &[Vec<i32>],
+    source: usize,
+    sink: usize,
+    observer: &mut O,
+) -> Result<i32, GraphError<usize, i32>> {
     let num_vertices = graph.len();
-    let mut residual_graph = graph.clone();
-    let mut parent = vec![None; num_vertices]; // Vetor que armazena o pai de cada vértice no augmenting path
-    
-    // Para debug - imprime o grafo inicial
-    println!("Grafo inicial:");
-    for k in 0..num_vertices {
-        println!("{:?}", residual_graph[k]);
+    for vertex in [source, sink] {
+        if vertex >= num_vertices {
+            return Err(GraphError::MissingVertex(vertex));
+        }
+    }
+    if source == sink {
+        return Err(GraphError::SameSourceAndSink(source));
     }
+    let mut residual_graph = graph.to_vec();
+    let mut parent = vec![None; num_vertices]; // Vetor que armazena o pai de cada vértice no augmenting path
+
+    observer.started(&residual_graph);
     let mut max_flow = 0; // Inicializa o fluxo máximo com 0.
 
     // Enquanto houver um augmenting path no grafo residual
     while bfs(&residual_graph, source, sink, &mut parent) {
         let mut path_flow = i32::MAX; // Define o valor inicial para o fluxo mínimo do augmenting path como infinito
+        let mut path = vec![sink];
 
         // Encontrar o fluxo mínimo ao longo do augmenting path encontrado
         let mut v = sink; // Começa pelo vértice de destino
         while v != source {
             let u = parent[v].unwrap(); // Obtém o pai
             path_flow = path_flow.min(residual_graph[u][v]); //Atualiza o fluxo mínimo
+            path.push(u);
             v = u; // Avança para o próximo vértice do augmenting path
         }
+        path.reverse();
+        observer.augmenting_path(&path, path_flow);
 
         // Atualizar as capacidades residuais do grafo residual
-        v = sink;  // Começa pelo vértice de destino
+        v = sink; // Começa pelo vértice de destino
         while v != source {
-            let u = parent[v].unwrap();  // Obtém o pai
-            residual_graph[u][v] -= path_flow; // Reduz a capacidade residual da aresta original            
+            let u = parent[v].unwrap(); // Obtém o pai
+            residual_graph[u][v] -= path_flow; // Reduz a capacidade residual da aresta original
             residual_graph[v][u] += path_flow; // Aumenta a capacidade residual da aresta reversa
             v = u; // Avança para o próximo vértice do augmenting path
         }
+        observer.residual_updated(&residual_graph);
 
-        // Imprime o grafo residual (mostra as capacidades restantes de cada aresta - normal e reversa)
-        println!("Grafo residual a cada iteração:");
-        for k in 0..num_vertices {
-        println!("{:?}", residual_graph[k]);
-    }
         // Incrementar o fluxo máximo
         max_flow += path_flow;
     }
-    
-    // Imprime o grafo residual (mostra as capacidades restantes de cada aresta - normal e reversa)
-    println!("");
-    println!("Grafo residual final:");
-    for k in 0..num_vertices {
-        println!("{:?}", residual_graph[k]);
-    }
-    max_flow // Retorna o fluxo máximo
+
+    observer.finished(max_flow, &residual_graph);
+    Ok(max_flow) // Retorna o fluxo máximo
 }
 
 // Busca em largura (BFS) para encontrar augmenting paths
-pub fn bfs(graph: &Vec<Vec<i32>>, source: usize, sink: usize, parent: &mut Vec<Option<usize>>) -> bool {
+fn bfs(graph: &[Vec<i32>], source: usize, sink: usize, parent: &mut [Option<usize>]) -> bool {
     let num_vertices = graph.len();
     let mut visited = vec![false; num_vertices];
 
@@ -73,16 +138,750 @@ pub fn bfs(graph: &Vec<Vec<i32>>, source: usize, sink: usize, parent: &mut Vec<O
                 parent[v] = Some(u);
                 queue.push_back(v);
             }
-        } 
+        }
     }
 
     visited[sink]
 }
 
+/// Result of a maximum flow computation over a Graph.
+/// Holds the total flow, the flow assigned to each edge of the graph
+/// (in the same order as `GraphStorage::iter_edges`) and the source side of the minimum cut.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaxFlow<V: GraphVertexTrait, E: GraphEdgeTrait> {
+    source: V,
+    sink: V,
+    value: E,
+    edge_flows: Vec<(V, V, E)>,
+    min_cut: BTreeSet<V>,
+}
+
+impl<V, E> MaxFlow<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + Sub<Output = E>,
+{
+    pub fn value(&self) -> E {
+        self.value
+    }
+
+    pub fn edge_flows(&self) -> &[(V, V, E)] {
+        &self.edge_flows
+    }
+
+    /// Total flow going from one vertex to another, summing parallel edges
+    pub fn flow(&self, from: V, to: V) -> E {
+        let mut total = E::default();
+        for (u, v, flow) in &self.edge_flows {
+            if *u == from && *v == to {
+                total += *flow;
+            }
+        }
+        total
+    }
+
+    /// Vertices on the source side of the minimum cut
+    pub fn min_cut(&self) -> &BTreeSet<V> {
+        &self.min_cut
+    }
+
+    /// Edges crossing the minimum cut, all of them saturated by the flow
+    pub fn cut_edges(&self) -> Vec<(V, V, E)> {
+        self.edge_flows
+            .iter()
+            .filter(|(u, v, _)| self.min_cut.contains(u) && !self.min_cut.contains(v))
+            .cloned()
+            .collect()
+    }
+
+    /// Decomposes the flow into source to sink paths, each with the amount it carries.
+    /// Flow running around cycles doesn't reach the sink, so it is left out.
+    pub fn decompose(&self) -> Vec<(Vec<V>, E)> {
+        let mut remaining: Vec<E> = self.edge_flows.iter().map(|edge| edge.2).collect();
+        let mut outgoing: BTreeMap<&V, Vec<usize>> = BTreeMap::new();
+        for (i, (u, _, _)) in self.edge_flows.iter().enumerate() {
+            outgoing.entry(u).or_default().push(i);
+        }
+
+        let mut paths = Vec::new();
+        loop {
+            // BFS over the edges still carrying flow, remembering the edge used to reach each vertex
+            let mut parent: BTreeMap<&V, usize> = BTreeMap::new();
+            let mut queue = VecDeque::new();
+            queue.push_back(&self.source);
+            while let Some(u) = queue.pop_front() {
+                if *u == self.sink {
+                    break;
+                }
+                for &i in outgoing.get(u).into_iter().flatten() {
+                    let v = &self.edge_flows[i].1;
+                    if remaining[i] > E::default() && *v != self.source && !parent.contains_key(v) {
+                        parent.insert(v, i);
+                        queue.push_back(v);
+                    }
+                }
+            }
+            if !parent.contains_key(&self.sink) {
+                break;
+            }
+
+            let mut used = Vec::new();
+            let mut v = &self.sink;
+            while *v != self.source {
+                let i = parent[v];
+                used.push(i);
+                v = &self.edge_flows[i].0;
+            }
+            used.reverse();
+
+            let amount = used.iter().map(|&i| remaining[i]).min().unwrap();
+            let mut path = vec![self.source.clone()];
+            for &i in &used {
+                remaining[i] = remaining[i] - amount;
+                path.push(self.edge_flows[i].1.clone());
+            }
+            paths.push((path, amount));
+        }
+        paths
+    }
+}
+
+/// Residual network used by the max flow algorithms over a Graph.
+/// Each graph edge becomes a pair of arcs, 2k (forward) and 2k + 1 (backward),
+/// so the reverse of arc `a` is always `a ^ 1`.
+struct FlowNetwork<V, E> {
+    vertices: Vec<V>,
+    adjacency: Vec<Vec<usize>>,
+    heads: Vec<usize>,
+    residual: Vec<E>,
+}
+
+impl<V, E> FlowNetwork<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + Sub<Output = E>,
+{
+    fn new<G>(graph: &G) -> Self
+    where
+        G: GraphStorage<Vertex = V, Edge = E, EdgeType = Directed>,
+    {
+        Self::with_capacity(graph, |_, _, value| *value)
+    }
+
+    /// Arc 2k is built from the k-th edge of `GraphStorage::iter_edges`, with the capacity given by the closure
+    fn with_capacity<G, F>(graph: &G, capacity: F) -> Self
+    where
+        G: GraphStorage<Vertex = V, Edge = E, EdgeType = Directed>,
+        F: Fn(&V, &V, &E) -> E,
+    {
+        let vertices: Vec<V> = graph.iter_vertices().cloned().collect();
+        let index: BTreeMap<&V, usize> = graph.iter_vertices().zip(0..).collect();
+        let mut network = FlowNetwork {
+            adjacency: vec![Vec::new(); vertices.len()],
+            vertices,
+            heads: Vec::new(),
+            residual: Vec::new(),
+        };
+        for (from, to, value) in graph.iter_edges() {
+            let (u, v) = (index[from], index[to]);
+            // Negative capacities make no sense for a flow, treat them as missing edges
+            let capacity = capacity(from, to, value).max(E::default());
+            network.adjacency[u].push(network.heads.len());
+            network.heads.push(v);
+            network.residual.push(capacity);
+            network.adjacency[v].push(network.heads.len());
+            network.heads.push(u);
+            network.residual.push(E::default());
+        }
+        network
+    }
+
+    fn index_of(&self, vertex: V) -> Result<usize, GraphError<V, E>> {
+        self.vertices
+            .binary_search(&vertex)
+            .map_err(|_| GraphError::MissingVertex(vertex))
+    }
+
+    /// Indices of source and sink, which must be two different vertices of the network
+    fn endpoints(&self, source: V, sink: V) -> Result<(usize, usize), GraphError<V, E>> {
+        let s = self.index_of(source)?;
+        let t = self.index_of(sink)?;
+        if s == t {
+            return Err(GraphError::SameSourceAndSink(self.vertices[s].clone()));
+        }
+        Ok((s, t))
+    }
+
+    fn push(&mut self, arc: usize, amount: E) {
+        self.residual[arc] = self.residual[arc] - amount;
+        self.residual[arc ^ 1] += amount;
+    }
+
+    /// Finds a shortest augmenting path, returning the arc used to reach each vertex
+    fn augmenting_path(&self, source: usize, sink: usize) -> Option<Vec<Option<usize>>> {
+        let mut parent = vec![None; self.vertices.len()];
+        let mut visited = vec![false; self.vertices.len()];
+        let mut queue = VecDeque::new();
+        visited[source] = true;
+        queue.push_back(source);
+        while let Some(u) = queue.pop_front() {
+            for &arc in &self.adjacency[u] {
+                let v = self.heads[arc];
+                if !visited[v] && self.residual[arc] > E::default() {
+                    visited[v] = true;
+                    parent[v] = Some(arc);
+                    if v == sink {
+                        return Some(parent);
+                    }
+                    queue.push_back(v);
+                }
+            }
+        }
+        None
+    }
+
+    /// The flow of edge k is what its backward arc 2k + 1 can send back
+    fn edge_flows(&self) -> Vec<(V, V, E)> {
+        (0..self.heads.len())
+            .step_by(2)
+            .map(|arc| {
+                let from = self.vertices[self.heads[arc + 1]].clone();
+                let to = self.vertices[self.heads[arc]].clone();
+                (from, to, self.residual[arc + 1])
+            })
+            .collect()
+    }
+
+    /// Builds the result once no augmenting path is left:
+    /// the flow of edge k sits on its backward arc, and the vertices still
+    /// reachable from the source in the residual network form the minimum cut.
+    fn into_max_flow(self, source: usize, sink: usize, value: E) -> MaxFlow<V, E> {
+        let mut reachable = vec![false; self.vertices.len()];
+        let mut queue = VecDeque::new();
+        reachable[source] = true;
+        queue.push_back(source);
+        while let Some(u) = queue.pop_front() {
+            for &arc in &self.adjacency[u] {
+                let v = self.heads[arc];
+                if !reachable[v] && self.residual[arc] > E::default() {
+                    reachable[v] = true;
+                    queue.push_back(v);
+                }
+            }
+        }
+
+        let edge_flows = self.edge_flows();
+        let min_cut = self
+            .vertices
+            .iter()
+            .zip(reachable)
+            .filter(|(_, reachable)| *reachable)
+            .map(|(v, _)| v.clone())
+            .collect();
+
+        MaxFlow {
+            source: self.vertices[source].clone(),
+            sink: self.vertices[sink].clone(),
+            value,
+            edge_flows,
+            min_cut,
+        }
+    }
+}
+
+/// Maximum flow over a directed graph, whatever its storage, using the edge values as capacities.
+/// Uses Edmonds-Karp: augment along shortest paths of the residual network until there is none left.
+/// Fails with MissingVertex when source or sink are not in the graph,
+/// or with SameSourceAndSink when they are the same vertex.
+pub fn max_flow<V, E, G>(graph: &G, source: V, sink: V) -> Result<MaxFlow<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + Sub<Output = E>,
+    G: GraphStorage<Vertex = V, Edge = E, EdgeType = Directed>,
+{
+    let mut network = FlowNetwork::new(graph);
+    let (s, t) = network.endpoints(source, sink)?;
+
+    let mut value = E::default();
+    while let Some(parent) = network.augmenting_path(s, t) {
+        let mut path = Vec::new();
+        let mut v = t;
+        while let Some(arc) = parent[v] {
+            path.push(arc);
+            v = network.heads[arc ^ 1];
+        }
+        let bottleneck = path.iter().map(|&arc| network.residual[arc]).min().unwrap();
+        for arc in path {
+            network.push(arc, bottleneck);
+        }
+        value += bottleneck;
+    }
+
+    Ok(network.into_max_flow(s, t, value))
+}
+
+/// Dinic's algorithm, over the same Graph input and MaxFlow result as max_flow.
+/// Each phase builds the BFS level graph of the residual network and saturates it
+/// with a blocking flow, using a per-vertex arc pointer so no arc is scanned twice in a phase.
+/// O(V²E) in general, and much faster than Edmonds-Karp on large sparse networks.
+pub fn dinic<V, E, G>(graph: &G, source: V, sink: V) -> Result<MaxFlow<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + Sub<Output = E>,
+    G: GraphStorage<Vertex = V, Edge = E, EdgeType = Directed>,
+{
+    let mut network = FlowNetwork::new(graph);
+    let (s, t) = network.endpoints(source, sink)?;
+    let n = network.vertices.len();
+
+    let mut value = E::default();
+    loop {
+        // Level graph: distance from the source through arcs with residual capacity
+        let mut level = vec![usize::MAX; n];
+        let mut queue = VecDeque::new();
+        level[s] = 0;
+        queue.push_back(s);
+        while let Some(u) = queue.pop_front() {
+            for &arc in &network.adjacency[u] {
+                let v = network.heads[arc];
+                if level[v] == usize::MAX && network.residual[arc] > E::default() {
+                    level[v] = level[u] + 1;
+                    queue.push_back(v);
+                }
+            }
+        }
+        if level[t] == usize::MAX {
+            break;
+        }
+
+        // Blocking flow, with an explicit stack so deep networks don't overflow the call stack
+        let mut next_arc = vec![0; n];
+        let mut path: Vec<usize> = Vec::new();
+        let mut u = s;
+        loop {
+            if u == t {
+                let bottleneck = path.iter().map(|&arc| network.residual[arc]).min().unwrap();
+                for &arc in &path {
+                    network.push(arc, bottleneck);
+                }
+                value += bottleneck;
+                path.clear();
+                u = s;
+                continue;
+            }
+
+            let mut advanced = false;
+            while next_arc[u] < network.adjacency[u].len() {
+                let arc = network.adjacency[u][next_arc[u]];
+                let v = network.heads[arc];
+                if network.residual[arc] > E::default() && level[v] == level[u] + 1 {
+                    path.push(arc);
+                    u = v;
+                    advanced = true;
+                    break;
+                }
+                next_arc[u] += 1;
+            }
+
+            if !advanced {
+                // Dead end: nothing more goes through u in this phase
+                if u == s {
+                    break;
+                }
+                level[u] = usize::MAX;
+                let arc = path.pop().unwrap();
+                u = network.heads[arc ^ 1];
+                next_arc[u] += 1;
+            }
+        }
+    }
+
+    Ok(network.into_max_flow(s, t, value))
+}
+
+/// Highest-label push-relabel, over the same Graph input and MaxFlow result as max_flow.
+/// Instead of augmenting paths, vertices hold an excess of flow and push it downhill,
+/// always discharging the highest active vertex first. The gap heuristic lifts every
+/// vertex above an empty height straight out of the sink's reach.
+/// O(V²√E), and it is usually the fastest choice on dense networks.
+pub fn push_relabel<V, E, G>(
+    graph: &G,
+    source: V,
+    sink: V,
+) -> Result<MaxFlow<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + Sub<Output = E>,
+    G: GraphStorage<Vertex = V, Edge = E, EdgeType = Directed>,
+{
+    let mut network = FlowNetwork::new(graph);
+    let (s, t) = network.endpoints(source, sink)?;
+    let n = network.vertices.len();
+
+    // Heights never go beyond 2V - 1, so buckets and counters are sized for 2V
+    let mut height = vec![0; n];
+    let mut count = vec![0; 2 * n];
+    let mut excess = vec![E::default(); n];
+    let mut next_arc = vec![0; n];
+    let mut active: Vec<Vec<usize>> = vec![Vec::new(); 2 * n];
+    let mut highest = 0;
+    height[s] = n;
+    count[0] = n - 1;
+    count[n] = 1;
+
+    // Saturate every arc leaving the source
+    for i in 0..network.adjacency[s].len() {
+        let arc = network.adjacency[s][i];
+        let capacity = network.residual[arc];
+        let v = network.heads[arc];
+        if capacity > E::default() && v != s {
+            network.push(arc, capacity);
+            if v != t && excess[v] == E::default() {
+                active[0].push(v);
+            }
+            excess[v] += capacity;
+        }
+    }
+
+    loop {
+        while highest > 0 && active[highest].is_empty() {
+            highest -= 1;
+        }
+        let u = match active[highest].pop() {
+            Some(u) => u,
+            None => break,
+        };
+        if height[u] != highest {
+            // Lifted by a gap after it was queued, file it under its current height
+            highest = highest.max(height[u]);
+            active[height[u]].push(u);
+            continue;
+        }
+
+        // Discharge u
+        while excess[u] > E::default() {
+            if next_arc[u] == network.adjacency[u].len() {
+                // Relabel: just above the lowest neighbour we can still push to
+                let old = height[u];
+                let mut new = network.adjacency[u]
+                    .iter()
+                    .filter(|&&arc| network.residual[arc] > E::default())
+                    .map(|&arc| height[network.heads[arc]] + 1)
+                    .min()
+                    .unwrap_or(2 * n - 1)
+                    .min(2 * n - 1);
+                count[old] -= 1;
+                if count[old] == 0 && old < n {
+                    // Gap: nothing above old can reach the sink anymore
+                    for w in 0..n {
+                        if height[w] > old && height[w] < n {
+                            count[height[w]] -= 1;
+                            height[w] = n + 1;
+                            count[n + 1] += 1;
+                            next_arc[w] = 0;
+                        }
+                    }
+                    new = new.max(n + 1);
+                }
+                height[u] = new;
+                count[new] += 1;
+                next_arc[u] = 0;
+                continue;
+            }
+
+            let arc = network.adjacency[u][next_arc[u]];
+            let v = network.heads[arc];
+            if network.residual[arc] > E::default() && height[u] == height[v] + 1 {
+                let amount = excess[u].min(network.residual[arc]);
+                network.push(arc, amount);
+                excess[u] = excess[u] - amount;
+                if v != s && v != t && excess[v] == E::default() {
+                    active[height[v]].push(v);
+                    highest = highest.max(height[v]);
+                }
+                excess[v] += amount;
+            } else {
+                next_arc[u] += 1;
+            }
+        }
+    }
+
+    let value = excess[t];
+    Ok(network.into_max_flow(s, t, value))
+}
+
+/// Result of a minimum cost flow computation over a Graph.
+/// Holds how much flow was sent, its total cost and the flow assigned to each
+/// edge of the graph (in the same order as `GraphStorage::iter_edges`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinCostFlow<V: GraphVertexTrait, E: GraphEdgeTrait> {
+    value: E,
+    cost: E,
+    edge_flows: Vec<(V, V, E)>,
+}
+
+impl<V, E> MinCostFlow<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+    pub fn value(&self) -> E {
+        self.value
+    }
+
+    pub fn cost(&self) -> E {
+        self.cost
+    }
+
+    pub fn edge_flows(&self) -> &[(V, V, E)] {
+        &self.edge_flows
+    }
+
+    /// Total flow going from one vertex to another, summing parallel edges
+    pub fn flow(&self, from: V, to: V) -> E {
+        let mut total = E::default();
+        for (u, v, flow) in &self.edge_flows {
+            if *u == from && *v == to {
+                total += *flow;
+            }
+        }
+        total
+    }
+}
+
+/// Minimum cost maximum flow over a directed graph, whatever its storage.
+/// The edge values are the cost of sending one unit of flow through the edge,
+/// and the capacity of each edge is given by the closure.
+/// Fails like max_flow does on bad source or sink, and with the cycle itself
+/// when the network has a cycle of negative cost.
+pub fn min_cost_max_flow<V, E, G, F>(
+    graph: &G,
+    source: V,
+    sink: V,
+    capacity: F,
+) -> Result<MinCostFlow<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + Sub<Output = E> + Mul<Output = E>,
+    G: GraphStorage<Vertex = V, Edge = E, EdgeType = Directed>,
+    F: Fn(&V, &V, &E) -> E,
+{
+    min_cost_flow(graph, source, sink, E::max_value(), capacity)
+}
+
+/// Minimum cost flow sending at most `amount` from source to sink, see min_cost_max_flow.
+/// Successive shortest paths: Bellman-Ford computes the initial vertex potentials,
+/// so negative costs are fine, then each augmenting path is the cheapest one found
+/// by Dijkstra over the reduced costs, which the potentials keep non-negative.
+pub fn min_cost_flow<V, E, G, F>(
+    graph: &G,
+    source: V,
+    sink: V,
+    amount: E,
+    capacity: F,
+) -> Result<MinCostFlow<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + Sub<Output = E> + Mul<Output = E>,
+    G: GraphStorage<Vertex = V, Edge = E, EdgeType = Directed>,
+    F: Fn(&V, &V, &E) -> E,
+{
+    let mut network = FlowNetwork::with_capacity(graph, capacity);
+    let (s, t) = network.endpoints(source, sink)?;
+    let n = network.vertices.len();
+    let zero = E::default();
+    let infinity = E::max_value();
+
+    // Arc 2k costs what edge k does, and sending flow back refunds it
+    let mut costs = Vec::with_capacity(network.heads.len());
+    for (_, _, &cost) in graph.iter_edges() {
+        costs.push(cost);
+        costs.push(zero - cost);
+    }
+
+    // Bellman-Ford over the arcs with capacity
+    let mut potential = vec![infinity; n];
+    let mut via: Vec<Option<usize>> = vec![None; n];
+    potential[s] = zero;
+    for round in 0..n {
+        let mut relaxed = None;
+        for (arc, cost) in costs.iter().enumerate() {
+            let u = network.heads[arc ^ 1];
+            let v = network.heads[arc];
+            if network.residual[arc] <= zero || potential[u] == infinity {
+                continue;
+            }
+            if let Some(distance) = potential[u].checked_add(cost) {
+                if distance < potential[v] {
+                    potential[v] = distance;
+                    via[v] = Some(arc);
+                    relaxed = Some(v);
+                }
+            }
+        }
+        match relaxed {
+            None => break,
+            Some(v) if round == n - 1 => {
+                return Err(negative_cost_cycle(&network, &costs, &via, v).into())
+            }
+            Some(_) => {}
+        }
+    }
+
+    let mut value = zero;
+    let mut cost = zero;
+    while value < amount {
+        // Dijkstra over the reduced costs, remembering the arc used to reach each vertex
+        let mut distance = vec![infinity; n];
+        let mut parent: Vec<Option<usize>> = vec![None; n];
+        let mut prio = BinaryHeap::new();
+        distance[s] = zero;
+        prio.push(Reverse((zero, s)));
+        while let Some(Reverse((dist, u))) = prio.pop() {
+            if dist > distance[u] {
+                continue;
+            }
+            for &arc in &network.adjacency[u] {
+                let v = network.heads[arc];
+                if network.residual[arc] <= zero || potential[v] == infinity {
+                    continue;
+                }
+                let reduced = costs[arc] + potential[u] - potential[v];
+                if let Some(next) = dist.checked_add(&reduced) {
+                    if next < distance[v] {
+                        distance[v] = next;
+                        parent[v] = Some(arc);
+                        prio.push(Reverse((next, v)));
+                    }
+                }
+            }
+        }
+        if distance[t] == infinity {
+            break;
+        }
+        for v in 0..n {
+            if distance[v] != infinity {
+                potential[v] += distance[v];
+            }
+        }
+
+        let mut path = Vec::new();
+        let mut v = t;
+        while let Some(arc) = parent[v] {
+            path.push(arc);
+            v = network.heads[arc ^ 1];
+        }
+        let mut bottleneck = amount - value;
+        for &arc in &path {
+            bottleneck = bottleneck.min(network.residual[arc]);
+        }
+        for &arc in &path {
+            network.push(arc, bottleneck);
+            cost += bottleneck * costs[arc];
+        }
+        value += bottleneck;
+    }
+
+    Ok(MinCostFlow {
+        value,
+        cost,
+        edge_flows: network.edge_flows(),
+    })
+}
+
+/// Rebuilds the cycle of negative cost from the arcs Bellman-Ford relaxed with, the same way
+/// single_path does: walking back |V| arcs from a vertex relaxed on the last round lands on it
+fn negative_cost_cycle<V, E>(
+    network: &FlowNetwork<V, E>,
+    costs: &[E],
+    via: &[Option<usize>],
+    relaxed: usize,
+) -> NegativeCycle<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait + Sub<Output = E>,
+{
+    let tail = |v: usize| network.heads[via[v].unwrap() ^ 1];
+    let mut start = relaxed;
+    for _ in 0..via.len() {
+        start = tail(start);
+    }
+
+    let mut cycle = vec![network.vertices[start].clone()];
+    let mut weight = costs[via[start].unwrap()];
+    let mut current = tail(start);
+    while current != start {
+        cycle.push(network.vertices[current].clone());
+        weight += costs[via[current].unwrap()];
+        current = tail(current);
+    }
+    cycle.reverse();
+    NegativeCycle::new(cycle, weight)
+}
+
 #[cfg(test)]
 mod test_flow {
-    use crate::max_flow::edmonds_karp;
-    
+    use crate::max_flow::{
+        dinic, edmonds_karp, edmonds_karp_observed, max_flow, min_cost_flow, min_cost_max_flow,
+        push_relabel, FlowObserver, MaxFlow, ResidualPrinter,
+    };
+    use crate::{Graph, GraphError};
+    use std::collections::HashMap;
+
+    fn assert_valid_flow(
+        matrix: &[Vec<i32>],
+        flow: &MaxFlow<usize, i32>,
+        source: usize,
+        sink: usize,
+    ) {
+        let n = matrix.len();
+        for (u, v, f) in flow.edge_flows() {
+            assert!(*f >= 0 && *f <= matrix[*u][*v]);
+        }
+        for v in (0..n).filter(|v| *v != source && *v != sink) {
+            let incoming: i32 = (0..n).map(|u| flow.flow(u, v)).sum();
+            let outgoing: i32 = (0..n).map(|w| flow.flow(v, w)).sum();
+            assert_eq!(incoming, outgoing);
+        }
+        let into_sink: i32 = (0..n)
+            .map(|u| flow.flow(u, sink) - flow.flow(sink, u))
+            .sum();
+        assert_eq!(flow.value(), into_sink);
+        let cut: i32 = flow
+            .cut_edges()
+            .iter()
+            .map(|(u, v, _)| matrix[*u][*v])
+            .sum();
+        assert_eq!(flow.value(), cut);
+    }
+
+    fn cross_check(matrix: &[Vec<i32>], source: usize, sink: usize) -> i32 {
+        let expected = edmonds_karp(matrix, source, sink).unwrap();
+        let graph = graph_from_matrix(matrix);
+        for flow in [
+            max_flow(&graph, source, sink).unwrap(),
+            dinic(&graph, source, sink).unwrap(),
+            push_relabel(&graph, source, sink).unwrap(),
+        ] {
+            assert_eq!(expected, flow.value());
+            assert_valid_flow(matrix, &flow, source, sink);
+        }
+        expected
+    }
+
+    fn graph_from_matrix(matrix: &[Vec<i32>]) -> Graph<usize, i32> {
+        let mut graph = Graph::new();
+        for (u, row) in matrix.iter().enumerate() {
+            graph.add_vertex(u);
+            for (v, capacity) in row.iter().enumerate() {
+                if *capacity > 0 {
+                    graph.add_edge(u, v, *capacity);
+                }
+            }
+        }
+        graph
+    }
+
     #[test]
     fn test_flow_edmondskarp_preto_ppt() {
         // Grafo de exemplo
@@ -98,9 +897,10 @@ mod test_flow {
         let source = 0;
         let sink = 5;
 
-        let max_flow = edmonds_karp(&graph, source, sink);
+        let max_flow = edmonds_karp(&graph, source, sink).unwrap();
 
         println!("Fluxo máximo: {}", max_flow);
+        assert_eq!(20, max_flow);
     }
 
     #[test]
@@ -116,9 +916,10 @@ mod test_flow {
         let source = 0;
         let sink = 3;
 
-        let max_flow = edmonds_karp(&graph, source, sink);
+        let max_flow = edmonds_karp(&graph, source, sink).unwrap();
 
         println!("Fluxo máximo: {}", max_flow);
+        assert_eq!(200, max_flow);
     }
 
     #[test]
@@ -136,9 +937,10 @@ mod test_flow {
         let source = 0;
         let sink = 5;
 
-        let max_flow = edmonds_karp(&graph, source, sink);
+        let max_flow = edmonds_karp(&graph, source, sink).unwrap();
 
         println!("Fluxo máximo: {}", max_flow);
+        assert_eq!(23, max_flow);
     }
 
     #[test]
@@ -156,8 +958,352 @@ mod test_flow {
         let source = 0;
         let sink = 5;
 
-        let max_flow = edmonds_karp(&graph, source, sink);
+        let max_flow = edmonds_karp(&graph, source, sink).unwrap();
 
         println!("Fluxo máximo: {}", max_flow);
+        assert_eq!(26, max_flow);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_max_flow_livro() {
+        // Grafo igual ao do livro do Cormen
+        let matrix = vec![
+            vec![0, 16, 13, 0, 0, 0],
+            vec![0, 0, 0, 12, 0, 0],
+            vec![0, 4, 0, 0, 14, 0],
+            vec![0, 0, 9, 0, 0, 20],
+            vec![0, 0, 0, 7, 0, 4],
+            vec![0, 0, 0, 0, 0, 0],
+        ];
+        let graph = graph_from_matrix(&matrix);
+
+        let flow = max_flow(&graph, 0, 5).unwrap();
+        assert_eq!(23, flow.value());
+        assert_eq!(edmonds_karp(&matrix, 0, 5), Ok(flow.value()));
+
+        // The flow is conserved at every inner vertex and respects the capacities
+        for v in 1..5 {
+            let incoming: i32 = (0..6).map(|u| flow.flow(u, v)).sum();
+            let outgoing: i32 = (0..6).map(|w| flow.flow(v, w)).sum();
+            assert_eq!(incoming, outgoing);
+        }
+        for (u, v, f) in flow.edge_flows() {
+            assert!(*f >= 0 && *f <= matrix[*u][*v]);
+        }
+
+        // The minimum cut has the same capacity as the flow
+        assert_eq!(
+            vec![0, 1, 2, 4],
+            flow.min_cut().iter().copied().collect::<Vec<_>>()
+        );
+        let cut: i32 = flow
+            .cut_edges()
+            .iter()
+            .map(|(u, v, _)| matrix[*u][*v])
+            .sum();
+        assert_eq!(23, cut);
+
+        let paths = flow.decompose();
+        assert_eq!(23, paths.iter().map(|(_, amount)| amount).sum::<i32>());
+        for (path, _) in &paths {
+            assert_eq!(Some(&0), path.first());
+            assert_eq!(Some(&5), path.last());
+        }
+    }
+
+    #[test]
+    fn test_max_flow_cities() {
+        let mut graph = Graph::new();
+        graph.add_edge("Vancouver", "Edmonton", 16);
+        graph.add_edge("Vancouver", "Calgary", 13);
+        graph.add_edge("Edmonton", "Saskatoon", 12);
+        graph.add_edge("Calgary", "Edmonton", 4);
+        graph.add_edge("Calgary", "Regina", 14);
+        graph.add_edge("Saskatoon", "Calgary", 9);
+        graph.add_edge("Saskatoon", "Winnipeg", 20);
+        graph.add_edge("Regina", "Saskatoon", 7);
+        graph.add_edge("Regina", "Winnipeg", 4);
+
+        let flow = max_flow(&graph, "Vancouver", "Winnipeg").unwrap();
+        assert_eq!(23, flow.value());
+        assert_eq!(12, flow.flow("Edmonton", "Saskatoon"));
+        assert!(!flow.min_cut().contains("Saskatoon"));
+
+        assert_eq!(
+            0,
+            max_flow(&graph, "Winnipeg", "Vancouver").unwrap().value()
+        );
+        assert_eq!(
+            Err(GraphError::MissingVertex("Toronto")),
+            max_flow(&graph, "Vancouver", "Toronto")
+        );
+        assert_eq!(
+            Err(GraphError::SameSourceAndSink("Vancouver")),
+            max_flow(&graph, "Vancouver", "Vancouver")
+        );
+    }
+
+    #[test]
+    fn test_max_flow_parallel_edges() {
+        let mut graph = Graph::new();
+        graph.add_edge('s', 'a', 3);
+        graph.add_edge('s', 'a', 2);
+        graph.add_edge('a', 't', 4);
+
+        let flow = max_flow(&graph, 's', 't').unwrap();
+        assert_eq!(4, flow.value());
+        assert_eq!(4, flow.flow('s', 'a'));
+        assert_eq!(vec![('a', 't', 4)], flow.cut_edges());
+        assert_eq!(
+            4,
+            flow.decompose()
+                .iter()
+                .map(|(_, amount)| amount)
+                .sum::<i32>()
+        );
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        paths: Vec<(Vec<usize>, i32)>,
+        updates: usize,
+        finished: Option<i32>,
+    }
+
+    impl FlowObserver for Recorder {
+        fn augmenting_path(&mut self, path: &[usize], path_flow: i32) {
+            self.paths.push((path.to_vec(), path_flow));
+        }
+
+        fn residual_updated(&mut self, _residual_graph: &[Vec<i32>]) {
+            self.updates += 1;
+        }
+
+        fn finished(&mut self, max_flow: i32, _residual_graph: &[Vec<i32>]) {
+            self.finished = Some(max_flow);
+        }
+    }
+
+    #[test]
+    fn test_flow_edmondskarp_observer() {
+        let graph = vec![
+            vec![0, 100, 100, 0],
+            vec![0, 0, 1, 100],
+            vec![0, 0, 0, 100],
+            vec![0, 0, 0, 0],
+        ];
+
+        let mut recorder = Recorder::default();
+        let max_flow = edmonds_karp_observed(&graph, 0, 3, &mut recorder).unwrap();
+
+        assert_eq!(200, max_flow);
+        assert_eq!(Some(200), recorder.finished);
+        assert_eq!(
+            vec![(vec![0, 1, 3], 100), (vec![0, 2, 3], 100)],
+            recorder.paths
+        );
+        assert_eq!(2, recorder.updates);
+
+        assert_eq!(
+            max_flow,
+            edmonds_karp_observed(&graph, 0, 3, &mut ResidualPrinter).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_flow_edmondskarp_errors() {
+        let graph = vec![vec![0, 5], vec![0, 0]];
+        assert_eq!(
+            Err(GraphError::MissingVertex(2)),
+            edmonds_karp(&graph, 2, 1)
+        );
+        assert_eq!(
+            Err(GraphError::MissingVertex(7)),
+            edmonds_karp(&graph, 0, 7)
+        );
+        assert_eq!(
+            Err(GraphError::SameSourceAndSink(1)),
+            edmonds_karp(&graph, 1, 1)
+        );
+        let mut recorder = Recorder::default();
+        assert!(edmonds_karp_observed(&[], 0, 0, &mut recorder).is_err());
+        assert_eq!(None, recorder.finished);
+        assert_eq!(Ok(5), edmonds_karp(&graph, 0, 1));
+    }
+
+    #[test]
+    fn test_dinic_and_push_relabel_cormen() {
+        // Grafo igual ao do livro do Cormen
+        let livro = vec![
+            vec![0, 16, 13, 0, 0, 0],
+            vec![0, 0, 0, 12, 0, 0],
+            vec![0, 4, 0, 0, 14, 0],
+            vec![0, 0, 9, 0, 0, 20],
+            vec![0, 0, 0, 7, 0, 4],
+            vec![0, 0, 0, 0, 0, 0],
+        ];
+        assert_eq!(23, cross_check(&livro, 0, 5));
+        assert_eq!(0, cross_check(&livro, 5, 0));
+        assert_eq!(11, cross_check(&livro, 2, 3));
+
+        let preto_ppt = vec![
+            vec![0, 10, 10, 0, 0, 0],
+            vec![0, 0, 0, 25, 0, 0],
+            vec![0, 0, 0, 0, 15, 0],
+            vec![0, 0, 0, 0, 0, 10],
+            vec![0, 6, 0, 0, 0, 10],
+            vec![0, 0, 0, 0, 0, 0],
+        ];
+        assert_eq!(20, cross_check(&preto_ppt, 0, 5));
+
+        let losango = vec![
+            vec![0, 100, 100, 0],
+            vec![0, 0, 1, 100],
+            vec![0, 0, 0, 100],
+            vec![0, 0, 0, 0],
+        ];
+        assert_eq!(200, cross_check(&losango, 0, 3));
+
+        let exemplo = vec![
+            vec![0, 22, 0, 4, 0, 0],
+            vec![0, 0, 20, 7, 0, 0],
+            vec![0, 0, 0, 0, 0, 15],
+            vec![0, 0, 0, 0, 18, 0],
+            vec![0, 0, 13, 0, 0, 20],
+            vec![0, 0, 0, 0, 0, 0],
+        ];
+        assert_eq!(26, cross_check(&exemplo, 0, 5));
+    }
+
+    #[test]
+    fn test_dinic_and_push_relabel_generated() {
+        // Pseudo-random dense networks, so the cross check doesn't depend on hand made examples
+        let mut seed: u64 = 42;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 33) as i32
+        };
+        for size in [2, 7, 20, 45] {
+            let mut matrix = vec![vec![0; size]; size];
+            for (u, row) in matrix.iter_mut().enumerate() {
+                for (v, capacity) in row.iter_mut().enumerate() {
+                    if u != v && next() % 3 == 0 {
+                        *capacity = next() % 50 + 1;
+                    }
+                }
+            }
+            cross_check(&matrix, 0, size - 1);
+        }
+    }
+
+    #[test]
+    fn test_dinic_and_push_relabel_cities() {
+        let mut graph = Graph::new();
+        graph.add_edge("Vancouver", "Edmonton", 16);
+        graph.add_edge("Vancouver", "Calgary", 13);
+        graph.add_edge("Edmonton", "Saskatoon", 12);
+        graph.add_edge("Calgary", "Edmonton", 4);
+        graph.add_edge("Calgary", "Regina", 14);
+        graph.add_edge("Saskatoon", "Calgary", 9);
+        graph.add_edge("Saskatoon", "Winnipeg", 20);
+        graph.add_edge("Regina", "Saskatoon", 7);
+        graph.add_edge("Regina", "Winnipeg", 4);
+
+        let flow = dinic(&graph, "Vancouver", "Winnipeg").unwrap();
+        assert_eq!(23, flow.value());
+        let flow = push_relabel(&graph, "Vancouver", "Winnipeg").unwrap();
+        assert_eq!(23, flow.value());
+        assert!(flow.min_cut().contains("Regina"));
+        assert!(dinic(&graph, "Vancouver", "Toronto").is_err());
+        assert!(push_relabel(&graph, "Vancouver", "Vancouver").is_err());
+    }
+
+    #[test]
+    fn test_min_cost_assignment() {
+        let costs = [[9, 2, 7], [6, 4, 3], [5, 8, 1]];
+        let workers = ["w1", "w2", "w3"];
+        let jobs = ["j1", "j2", "j3"];
+        let mut graph = Graph::new();
+        for (i, worker) in workers.iter().enumerate() {
+            graph.add_edge("s", *worker, 0);
+            for (j, job) in jobs.iter().enumerate() {
+                graph.add_edge(*worker, *job, costs[i][j]);
+            }
+        }
+        for job in jobs {
+            graph.add_edge(job, "t", 0);
+        }
+
+        let flow = min_cost_max_flow(&graph, "s", "t", |_, _, _| 1).unwrap();
+        assert_eq!(3, flow.value());
+        assert_eq!(9, flow.cost());
+        assert_eq!(1, flow.flow("w1", "j2"));
+        assert_eq!(1, flow.flow("w2", "j1"));
+        assert_eq!(1, flow.flow("w3", "j3"));
+
+        // Only two jobs to be done: the two cheapest assignments that don't collide
+        let flow = min_cost_flow(&graph, "s", "t", 2, |_, _, _| 1).unwrap();
+        assert_eq!(2, flow.value());
+        assert_eq!(3, flow.cost());
+    }
+
+    #[test]
+    fn test_min_cost_transport() {
+        let capacities: HashMap<(&str, &str), i32> = [
+            (("factory", "port"), 10),
+            (("factory", "rail"), 6),
+            (("port", "city"), 8),
+            (("rail", "city"), 9),
+            (("port", "rail"), 4),
+        ]
+        .into_iter()
+        .collect();
+        let mut graph = Graph::new();
+        graph.add_edge("factory", "port", 1);
+        graph.add_edge("factory", "rail", 4);
+        graph.add_edge("port", "city", 5);
+        graph.add_edge("rail", "city", 1);
+        graph.add_edge("port", "rail", 1);
+
+        let capacity = |from: &&str, to: &&str, _: &i32| capacities[&(*from, *to)];
+        let flow = min_cost_max_flow(&graph, "factory", "city", capacity).unwrap();
+        assert_eq!(16, flow.value());
+        // rail-city has room for 3 units coming from the port, the other 7 take port-city
+        assert_eq!(3, flow.flow("port", "rail"));
+        assert_eq!(7, flow.flow("port", "city"));
+        assert_eq!(81, flow.cost());
+        for (from, to, f) in flow.edge_flows() {
+            assert!(*f <= capacities[&(*from, *to)]);
+        }
+    }
+
+    #[test]
+    fn test_min_cost_negative_costs() {
+        let mut graph = Graph::new();
+        graph.add_edge(0, 1, 2);
+        graph.add_edge(0, 2, 4);
+        graph.add_edge(1, 2, -3);
+        graph.add_edge(1, 3, 6);
+        graph.add_edge(2, 3, 1);
+
+        let flow = min_cost_max_flow(&graph, 0, 3, |_, _, _| 2).unwrap();
+        assert_eq!(4, flow.value());
+        // 0-1-2-3 is free, but 2-3 is also the only way out of 2 for the max flow
+        assert_eq!(2 * 8 + 2 * 5, flow.cost());
+        assert_eq!(0, flow.flow(1, 2));
+
+        graph.add_edge(2, 1, 1);
+        let Err(GraphError::NegativeCycle(cycle)) = min_cost_max_flow(&graph, 0, 3, |_, _, _| 2)
+        else {
+            panic!("expected a negative cycle")
+        };
+        assert_eq!(-2, cycle.weight());
+        assert!(cycle.contains(1) && cycle.contains(2));
+        assert_eq!(
+            Err(GraphError::MissingVertex(4)),
+            min_cost_max_flow(&graph, 0, 4, |_, _, _| 2)
+        );
+    }
+}
@@ -1,59 +1,138 @@
-use super::{EdgeComparator, EdgeTypeTrait, Graph, GraphEdgeTrait, GraphVertexTrait};
-use std::cmp::Ordering;
+use super::{
+    EdgeComparator, GraphEdgeTrait, GraphError, GraphPayloadTrait, GraphStorage, GraphVertexTrait,
+};
 use std::cmp::Reverse;
-use std::collections::BinaryHeap;
-use union_find_rs::prelude::*;
+use std::collections::{BinaryHeap, HashMap};
 
-/// Minimal Spanning Tree algorithms.
+// Minimal Spanning Tree algorithms.
+
+/// Total weight of a spanning tree (or forest) along with its edges, as (from, to) pairs
+pub type SpanningTree<V, E> = (E, Vec<(V, V)>);
 
 /// Kruskal (Union-Find over a DisjointSet)
-///     Make Set using a DisjointSet for each vertex.
+///     Make Set using a DisjointSet for each vertex (by index, so vertices don't need to be Copy).
 ///     Sort edges by ascending edge weight
 ///     Loop over sorted edges
 ///     If vertices are Unified, don't inclued vertices
 ///     Else, Unify those two edges
 ///     Terminate when all edges have been processed, or all vertices have been Unified
-pub fn kruskal<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
-    graph: &Graph<V, E, T>,
-) -> Option<(E, Vec<(V, V)>)> {
-    let edges = graph.edges_with_weights(Ordering::Less);
-    let mut sets: DisjointSets<V> = DisjointSets::new();
+/// A graph that isn't connected gets a spanning forest. Fails with EmptyGraph when there are no vertices.
+pub fn kruskal<V, E, G>(graph: &G) -> Result<SpanningTree<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    kruskal_with(graph, |_, _, value| *value)
+}
+
+/// Kruskal over the weights the closure takes from the edge payloads
+pub fn kruskal_by<V, E, W, G, F>(
+    graph: &G,
+    weight: F,
+) -> Result<SpanningTree<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&E) -> W,
+{
+    kruskal_with(graph, |_, _, value| weight(value))
+}
+
+/// Kruskal with the weight of each edge computed by the closure from (from, to, value)
+pub fn kruskal_with<V, E, W, G, F>(
+    graph: &G,
+    weight: F,
+) -> Result<SpanningTree<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    if graph.vertices_count() == 0 {
+        return Err(GraphError::EmptyGraph);
+    }
+    let mut edges: Vec<(&V, &V, W)> = graph
+        .iter_edges()
+        .map(|(from, to, value)| (from, to, weight(from, to, value)))
+        .collect();
+    edges.sort_unstable_by_key(|(_, _, weight)| *weight);
     let mut result: Vec<(V, V)> = Vec::new();
-    let mut total_weight = E::default();
+    let mut total_weight = W::default();
 
     // Make set
-    for vertex in graph.vertices() {
-        sets.make_set(*vertex).unwrap();
-    }
+    let index: HashMap<&V, usize> = graph.iter_vertices().zip(0..).collect();
+    let mut sets = DisjointSets::new(index.len());
 
     // Loop over all edges in ascending sort order
     for (from, to, weight) in &edges {
-        if sets.find_set(from).unwrap() != sets.find_set(to).unwrap() {
-            sets.union(from, to).unwrap();
-            result.push((*from, *to));
+        if sets.union(index[from], index[to]) {
+            result.push(((*from).clone(), (*to).clone()));
             total_weight += *weight;
         }
     }
 
-    if result.is_empty() {
-        None
-    } else {
-        Some((total_weight, result))
-    }
+    Ok((total_weight, result))
 }
 
 /// Prim
-pub fn prim<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
-    graph: &Graph<V, E, T>,
+/// Spans the vertices reachable from start. Fails with MissingVertex when start isn't in the graph.
+pub fn prim<V, E, G>(graph: &G, start: V) -> Result<SpanningTree<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    prim_with(graph, start, |_, _, value| *value)
+}
+
+/// Prim over the weights the closure takes from the edge payloads
+pub fn prim_by<V, E, W, G, F>(
+    graph: &G,
+    start: V,
+    weight: F,
+) -> Result<SpanningTree<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&E) -> W,
+{
+    prim_with(graph, start, |_, _, value| weight(value))
+}
+
+/// Prim with the weight of each edge computed by the closure from (from, to, value)
+pub fn prim_with<V, E, W, G, F>(
+    graph: &G,
     start: V,
-) -> Option<(E, Vec<(V, V)>)> {
+    weight: F,
+) -> Result<SpanningTree<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    if !graph.contains_vertex(&start) {
+        return Err(GraphError::MissingVertex(start));
+    }
     let mut prio = BinaryHeap::new();
     let mut visited: Vec<V> = Vec::new();
     let mut result: Vec<(V, V)> = Vec::new();
-    let mut total_weight = E::default();
-
-    for adjancent in graph.get_adjacent_vertices(start).unwrap_or(&vec![]) {
-        prio.push(Reverse(EdgeComparator(adjancent.0, start, adjancent.1)));
+    let mut total_weight = W::default();
+
+    for adjancent in graph.neighbours(&start) {
+        prio.push(Reverse(EdgeComparator(
+            adjancent.0.clone(),
+            start.clone(),
+            weight(&start, adjancent.0, adjancent.1),
+        )));
     }
 
     visited.push(start);
@@ -63,31 +142,67 @@ pub fn prim<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
             continue;
         }
 
-        visited.push(target);
+        for (new_target, value) in graph.neighbours(&target) {
+            if !visited.contains(new_target) {
+                prio.push(Reverse(EdgeComparator(
+                    new_target.clone(),
+                    target.clone(),
+                    weight(&target, new_target, value),
+                )));
+            }
+        }
+        visited.push(target.clone());
         result.push((prev, target));
         total_weight += dist;
+    }
 
-        for (new_target, cost) in graph.get_adjacent_vertices(target).unwrap_or(&vec![]) {
-            if !visited.contains(new_target) {
-                prio.push(Reverse(EdgeComparator(*new_target, target, *cost)));
-            }
+    Ok((total_weight, result))
+}
+
+/// Union-Find over vertex indices, with path halving and union by size
+struct DisjointSets {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl DisjointSets {
+    fn new(count: usize) -> Self {
+        DisjointSets {
+            parents: (0..count).collect(),
+            sizes: vec![1; count],
+        }
+    }
+
+    fn find(&mut self, mut element: usize) -> usize {
+        while self.parents[element] != element {
+            self.parents[element] = self.parents[self.parents[element]];
+            element = self.parents[element];
         }
+        element
     }
 
-    if visited.is_empty() {
-        None
-    } else {
-        Some((total_weight, result))
+    /// Joins the sets of both elements, returning false when they were already the same set
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.sizes[a] < self.sizes[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parents[b] = a;
+        self.sizes[a] += self.sizes[b];
+        true
     }
 }
 
 #[cfg(test)]
 mod test_mst {
-    use crate::{kruskal, prim};
+    use crate::{kruskal, kruskal_by, kruskal_with, prim, prim_by, prim_with, GraphError};
 
     #[test]
     fn test_cycle_mst_kruskal() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_vertex(1);
         graph.add_vertex(2);
         graph.add_vertex(3);
@@ -95,15 +210,15 @@ mod test_mst {
         graph.add_edge(2, 3, 9);
         graph.add_edge(3, 1, 1);
         let sort_kruskal = kruskal(&graph);
-        assert_eq!(false, sort_kruskal.is_none());
+        assert_eq!(false, sort_kruskal.is_err());
 
         let sort_prim = prim(&graph, 1);
-        assert_eq!(false, sort_prim.is_none());
+        assert_eq!(false, sort_prim.is_err());
     }
 
     #[test]
     fn test_cycle_mst_prim() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_vertex(1);
         graph.add_vertex(2);
         graph.add_vertex(3);
@@ -111,12 +226,12 @@ mod test_mst {
         graph.add_edge(2, 3, 9);
         graph.add_edge(3, 1, 1);
         let sort_prim = prim(&graph, 1);
-        assert_eq!(false, sort_prim.is_none());
+        assert_eq!(false, sort_prim.is_err());
     }
 
     #[test]
     fn test_ppt_graph_mst() {
-        let mut graph = super::Graph::new_undirected();
+        let mut graph = crate::Graph::new_undirected();
         graph.add_edge("A", "E", 1);
         graph.add_edge("I", "J", 0);
         graph.add_edge("A", "B", 5);
@@ -141,7 +256,7 @@ mod test_mst {
 
     #[test]
     fn test_ppt_1_graph_prim() {
-        let mut graph = super::Graph::new_undirected();
+        let mut graph = crate::Graph::new_undirected();
         graph.add_edge("A", "E", 1);
         graph.add_edge("I", "J", 0);
         graph.add_edge("A", "B", 5);
@@ -160,14 +275,14 @@ mod test_mst {
         graph.add_edge("H", "I", 6);
         graph.add_edge("G", "H", 1);
         graph.add_edge("F", "G", 7);
-        let sort = prim(&graph, &"A");
+        let sort = prim(&graph, "A");
         println!("{:?}", sort);
         assert_eq!(14, sort.unwrap().0);
     }
 
     #[test]
     fn test_ppt_2_graph_prim() {
-        let mut graph = super::Graph::new_undirected();
+        let mut graph = crate::Graph::new_undirected();
         graph.add_edge("0", "1", 10);
         graph.add_edge("0", "3", 4);
         graph.add_edge("0", "2", 1);
@@ -189,14 +304,14 @@ mod test_mst {
 
         graph.add_edge("6", "7", 12);
 
-        let sort = prim(&graph, &"0");
+        let sort = prim(&graph, "0");
         println!("{:?}", sort);
         assert_eq!(20, sort.unwrap().0);
     }
 
     #[test]
     fn test_disconnected_graph() {
-        let mut graph = super::Graph::new_undirected();
+        let mut graph = crate::Graph::new_undirected();
         graph.add_edge("0", "1", 10);
         graph.add_edge("0", "3", 4);
         graph.add_edge("0", "2", 1);
@@ -216,12 +331,73 @@ mod test_mst {
 
         graph.add_vertex("7");
 
-        let sort = prim(&graph, &"0");
+        let sort = prim(&graph, "0");
         println!("{:?}", sort);
         assert_eq!(12, sort.unwrap().0);
 
-        let sort_start = prim(&graph, &"7");
+        let sort_start = prim(&graph, "7");
         println!("{:?}", sort_start);
         assert_eq!(0, sort_start.unwrap().0);
     }
+
+    #[test]
+    fn test_string_vertices() {
+        let mut graph = crate::Graph::new_undirected();
+        for (from, to, value) in [("A", "B", 2), ("B", "C", 3), ("A", "C", 4), ("C", "D", 1)] {
+            graph.add_edge(from.to_string(), to.to_string(), value);
+        }
+        assert_eq!(6, kruskal(&graph).unwrap().0);
+        assert_eq!(6, prim(&graph, "A".to_string()).unwrap().0);
+    }
+
+    #[test]
+    fn test_edge_payloads() {
+        // Cable runs, laid along the cheapest ones rather than the shortest
+        let mut graph = crate::Graph::new_undirected();
+        graph.add_edge('a', 'b', ("fiber", 3, 90));
+        graph.add_edge('b', 'c', ("copper", 5, 20));
+        graph.add_edge('a', 'c', ("copper", 6, 25));
+
+        let (cost, edges) = kruskal_by(&graph, |(_, _, cost)| *cost).unwrap();
+        assert_eq!(45, cost);
+        assert_eq!(vec![('b', 'c'), ('a', 'c')], edges);
+        assert_eq!(45, prim_by(&graph, 'a', |(_, _, cost)| *cost).unwrap().0);
+        assert_eq!(8, prim_by(&graph, 'a', |(_, length, _)| *length).unwrap().0);
+    }
+
+    #[test]
+    fn test_weight_closure() {
+        let mut graph = crate::Graph::new_undirected();
+        graph.add_edge(1, 2, 1);
+        graph.add_edge(2, 3, 1);
+        graph.add_edge(1, 3, 1);
+        graph.add_edge(3, 4, 1);
+
+        // Links touching the hub at 3 are twice as expensive
+        let cost = |from: &i32, to: &i32, value: &i32| {
+            if *from == 3 || *to == 3 {
+                2 * value
+            } else {
+                *value
+            }
+        };
+        let (total, edges) = kruskal_with(&graph, cost).unwrap();
+        assert_eq!(5, total);
+        assert_eq!(3, edges.len());
+        assert!(edges.contains(&(1, 2)));
+        assert_eq!(5, prim_with(&graph, 1, cost).unwrap().0);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut graph: crate::Graph<i32, i32, crate::Undirected> = crate::Graph::new_undirected();
+        assert_eq!(Err(GraphError::EmptyGraph), kruskal(&graph));
+        assert_eq!(Err(GraphError::MissingVertex(1)), prim(&graph, 1));
+
+        // Without edges every vertex is its own tree
+        graph.add_vertex(1);
+        graph.add_vertex(2);
+        assert_eq!(Ok((0, vec![])), kruskal(&graph));
+        assert_eq!(Ok((0, vec![])), prim(&graph, 1));
+    }
 }
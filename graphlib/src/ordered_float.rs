@@ -0,0 +1,224 @@
+use num::{Bounded, CheckedAdd, Float, ToPrimitive};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+// Floating point edge weights.
+// f32 and f64 are neither Eq, Ord nor Hash, so they are wrapped in a type with a total order:
+// NaN is equal to itself and greater than everything else, and -0.0 equals 0.0.
+
+/// A float usable as an edge weight, e.g. `Graph<&str, OrderedFloat<f64>>`.
+/// The bounds are the infinities, and adding up to a non finite value counts as an overflow.
+#[derive(Debug, Clone, Copy)]
+pub struct OrderedFloat<F: Float>(pub F);
+
+impl<F: Float> OrderedFloat<F> {
+    pub fn into_inner(self) -> F {
+        self.0
+    }
+}
+
+impl<F: Float> From<F> for OrderedFloat<F> {
+    fn from(value: F) -> Self {
+        OrderedFloat(value)
+    }
+}
+
+impl<F: Float> Default for OrderedFloat<F> {
+    fn default() -> Self {
+        OrderedFloat(F::zero())
+    }
+}
+
+impl<F: Float + fmt::Display> fmt::Display for OrderedFloat<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<F: Float> PartialEq for OrderedFloat<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<F: Float> Eq for OrderedFloat<F> {}
+
+impl<F: Float> PartialOrd for OrderedFloat<F> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: Float> Ord for OrderedFloat<F> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.0.is_nan(), other.0.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => self.0.partial_cmp(&other.0).unwrap(),
+        }
+    }
+}
+
+impl<F: Float> Hash for OrderedFloat<F> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Values that compare equal must hash the same: every NaN is one value, and so are both zeros
+        if self.0.is_nan() {
+            F::nan().integer_decode().hash(state);
+        } else if self.0.is_zero() {
+            F::zero().integer_decode().hash(state);
+        } else {
+            self.0.integer_decode().hash(state);
+        }
+    }
+}
+
+impl<F: Float> Bounded for OrderedFloat<F> {
+    fn min_value() -> Self {
+        OrderedFloat(F::neg_infinity())
+    }
+
+    fn max_value() -> Self {
+        OrderedFloat(F::infinity())
+    }
+}
+
+impl<F: Float> Add for OrderedFloat<F> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        OrderedFloat(self.0 + other.0)
+    }
+}
+
+impl<F: Float> AddAssign for OrderedFloat<F> {
+    fn add_assign(&mut self, other: Self) {
+        self.0 = self.0 + other.0;
+    }
+}
+
+impl<F: Float> CheckedAdd for OrderedFloat<F> {
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        let sum = self.0 + other.0;
+        sum.is_finite().then_some(OrderedFloat(sum))
+    }
+}
+
+impl<F: Float> Sub for OrderedFloat<F> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        OrderedFloat(self.0 - other.0)
+    }
+}
+
+impl<F: Float> Mul for OrderedFloat<F> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        OrderedFloat(self.0 * other.0)
+    }
+}
+
+impl<F: Float> Neg for OrderedFloat<F> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        OrderedFloat(-self.0)
+    }
+}
+
+impl<F: Float> ToPrimitive for OrderedFloat<F> {
+    fn to_i64(&self) -> Option<i64> {
+        self.0.to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.0.to_u64()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        self.0.to_f64()
+    }
+}
+
+#[cfg(test)]
+mod test_ordered_float {
+    use super::OrderedFloat;
+    use crate::{dijkstra, floyd_warshall, kruskal, prim, Graph};
+    use std::cmp::Ordering;
+    use std::collections::HashSet;
+
+    fn of(value: f64) -> OrderedFloat<f64> {
+        OrderedFloat(value)
+    }
+
+    fn build() -> Graph<&'static str, OrderedFloat<f64>> {
+        let mut graph = Graph::new();
+        graph.add_edge("A", "B", of(1.5));
+        graph.add_edge("A", "C", of(0.25));
+        graph.add_edge("C", "B", of(0.5));
+        graph.add_edge("B", "D", of(2.75));
+        graph.add_edge("C", "D", of(4.0));
+        graph
+    }
+
+    #[test]
+    fn test_total_order() {
+        let mut values = [
+            of(f64::NAN),
+            of(1.0),
+            of(-0.0),
+            of(f64::NEG_INFINITY),
+            of(0.0),
+        ];
+        values.sort();
+        assert_eq!(of(f64::NEG_INFINITY), values[0]);
+        assert_eq!(of(1.0), values[3]);
+        assert!(values[4].into_inner().is_nan());
+        assert_eq!(of(f64::NAN), of(-f64::NAN));
+
+        let set: HashSet<_> = [of(0.0), of(-0.0), of(f64::NAN), of(f64::NAN)].into();
+        assert_eq!(2, set.len());
+    }
+
+    #[test]
+    fn test_shortest_paths() {
+        let graph = build();
+        let result = dijkstra(&graph, "A").unwrap();
+        assert_eq!(of(3.5), result.distances()[&"D"]);
+        assert_eq!(Some(vec!["A", "C", "B", "D"]), result.path_to("D"));
+
+        let all = floyd_warshall(&graph).unwrap();
+        assert_eq!(Some(of(3.5)), all.distance("A", "D"));
+        assert_eq!(Some(of(3.25)), all.distance("C", "D"));
+        assert_eq!(Some(of(f64::INFINITY)), all.distance("D", "A"));
+    }
+
+    #[test]
+    fn test_spanning_tree() {
+        let mut graph = Graph::new_undirected();
+        graph.add_edge("A", "B", of(0.1));
+        graph.add_edge("B", "C", of(0.2));
+        graph.add_edge("A", "C", of(0.25));
+        graph.add_edge("C", "D", of(1.5));
+
+        let (total, edges) = kruskal(&graph).unwrap();
+        assert!((total.into_inner() - 1.8).abs() < 1e-9);
+        assert_eq!(3, edges.len());
+        let (total, _) = prim(&graph, "A").unwrap();
+        assert!((total.into_inner() - 1.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_edges_with_weights() {
+        let graph = build();
+        let less = graph.edges_with_weights(Ordering::Less);
+        assert_eq!(("A", "C", of(0.25)), less[0]);
+        assert_eq!(("C", "D", of(4.0)), less[4]);
+        let greater = graph.edges_with_weights(Ordering::Greater);
+        assert_eq!(("C", "D", of(4.0)), greater[0]);
+    }
+}
@@ -1,54 +1,165 @@
-use super::{EdgeTypeTrait, Graph, GraphEdgeTrait, GraphVertexTrait};
+use super::{GraphEdgeTrait, GraphError, GraphStorage, GraphVertexTrait};
 use std::collections::HashMap;
 
-/// PageRank algorithm.
-/// 
-pub fn pagerank<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
-    graph: &Graph<V, E, T>, damping_factor: f64, epsilon: f64) -> HashMap<V, f64> {
-    let num_pages = graph.vertices_count() as f64;
-    let initial_rank = 1.0 / num_pages;
+/// Result of a PageRank computation: the rank of each vertex (summing up to 1),
+/// along with how the power iteration went.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRank<V: GraphVertexTrait> {
+    ranks: HashMap<V, f64>,
+    iterations: usize,
+    residual: f64,
+    converged: bool,
+}
 
-    let mut pagerank: HashMap<V, f64> = HashMap::new();
-    let mut new_pagerank: HashMap<V, f64> = HashMap::new();
+impl<V> PageRank<V>
+where
+    V: GraphVertexTrait,
+{
+    pub fn ranks(&self) -> &HashMap<V, f64> {
+        &self.ranks
+    }
 
-    for page in graph.vertices() {
-        pagerank.insert(*page, initial_rank);
+    pub fn into_ranks(self) -> HashMap<V, f64> {
+        self.ranks
     }
 
-    loop {
-        let mut diff = 0.0;
+    /// Number of power iterations performed
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
 
-        for (page, _) in graph.adj_list() {
-            let mut rank = 0.0;
+    /// L1 distance between the last two rank vectors
+    pub fn residual(&self) -> f64 {
+        self.residual
+    }
 
-            for incoming_pages in graph.get_incoming_vertices(*page).unwrap() {
-                let linked_count = graph.get_incoming_vertices(incoming_pages).unwrap().len() as f64;
-                rank += 1./linked_count * pagerank[&incoming_pages];
-            }
-            rank = (1.0 - damping_factor) / num_pages + damping_factor * rank;
-            new_pagerank.insert(*page, rank);
+    /// Whether the residual went below epsilon before max_iterations
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+}
 
-            diff += (rank - pagerank[page]).abs();
-        }
+/// PageRank algorithm.
+/// Each page splits its rank evenly among the pages it links to, and with probability
+/// 1 - damping_factor the surfer jumps to any page instead. Pages without outgoing links
+/// spread their rank over every page, so no rank leaks out of the graph.
+/// Iterates until the ranks move less than epsilon (L1), or max_iterations is reached.
+pub fn pagerank<V, E, G>(
+    graph: &G,
+    damping_factor: f64,
+    epsilon: f64,
+    max_iterations: usize,
+) -> Result<PageRank<V>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    personalized_pagerank(
+        graph,
+        &HashMap::new(),
+        damping_factor,
+        epsilon,
+        max_iterations,
+    )
+}
 
-        if diff < epsilon {
-            break;
+/// Personalized PageRank.
+/// Same as pagerank, but jumps (and the rank of pages without outgoing links) land on pages
+/// in proportion to the personalization weights. Pages missing from it get no jumps,
+/// and an empty (or all zero) personalization means every page is equally likely.
+/// Fails with MissingVertex when the personalization names a page that isn't in the graph.
+pub fn personalized_pagerank<V, E, G>(
+    graph: &G,
+    personalization: &HashMap<V, f64>,
+    damping_factor: f64,
+    epsilon: f64,
+    max_iterations: usize,
+) -> Result<PageRank<V>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    if let Some(page) = personalization
+        .keys()
+        .find(|page| !graph.contains_vertex(page))
+    {
+        return Err(GraphError::MissingVertex(page.clone()));
+    }
+    let num_pages = graph.vertices_count() as f64;
+
+    // Where the surfer lands when jumping, normalized to sum up to 1
+    let total: f64 = graph
+        .iter_vertices()
+        .filter_map(|page| personalization.get(page))
+        .sum();
+    let jump: HashMap<&V, f64> = graph
+        .iter_vertices()
+        .map(|page| {
+            let weight = if total > 0.0 {
+                personalization.get(page).copied().unwrap_or(0.0) / total
+            } else {
+                1.0 / num_pages
+            };
+            (page, weight)
+        })
+        .collect();
+
+    let mut pagerank: HashMap<&V, f64> = graph
+        .iter_vertices()
+        .map(|page| (page, 1.0 / num_pages))
+        .collect();
+    let mut iterations = 0;
+    let mut residual = 0.0;
+    let mut converged = graph.vertices_count() == 0;
+
+    while !converged && iterations < max_iterations {
+        iterations += 1;
+
+        // Rank held by pages with no outgoing links goes back to the jump targets
+        let mut dangling = 0.0;
+        let mut new_pagerank: HashMap<&V, f64> = pagerank.keys().map(|page| (*page, 0.0)).collect();
+        for page in graph.iter_vertices() {
+            let out_degree = graph.neighbours(page).count();
+            if out_degree == 0 {
+                dangling += pagerank[page];
+                continue;
+            }
+            let share = pagerank[page] / out_degree as f64;
+            for (linked, _) in graph.neighbours(page) {
+                *new_pagerank.get_mut(linked).unwrap() += share;
+            }
         }
 
-        for (page, rank) in new_pagerank.iter() {
-            pagerank.insert(*page, *rank);
+        residual = 0.0;
+        for (page, rank) in new_pagerank.iter_mut() {
+            *rank = damping_factor * (*rank + dangling * jump[page])
+                + (1.0 - damping_factor) * jump[page];
+            residual += (*rank - pagerank[page]).abs();
         }
 
-        new_pagerank.clear();
+        pagerank = new_pagerank;
+        converged = residual < epsilon;
     }
 
-    pagerank
+    Ok(PageRank {
+        ranks: pagerank
+            .into_iter()
+            .map(|(page, rank)| (page.clone(), rank))
+            .collect(),
+        iterations,
+        residual,
+        converged,
+    })
 }
 
 #[cfg(test)]
 mod test_pagerank {
     use super::pagerank;
-    use super::Graph;
+    use super::personalized_pagerank;
+    use crate::{Graph, GraphError};
+    use std::collections::HashMap;
 
     #[test]
     fn test_pagerank() {
@@ -56,25 +167,106 @@ mod test_pagerank {
         g.add_vertex("A");
         g.add_vertex("B");
         g.add_vertex("C");
-        g.add_vertex("D");        
+        g.add_vertex("D");
+        g.add_edge("A", "B", 0);
+        g.add_edge("A", "C", 0);
+        g.add_edge("A", "D", 0);
+
+        g.add_edge("B", "A", 0);
+        g.add_edge("B", "D", 0);
+
+        g.add_edge("C", "D", 0);
+
+        g.add_edge("D", "C", 0);
+        g.add_edge("D", "B", 0);
+
+        let result = pagerank(&g, 0.85, 0.0000001, 100).unwrap();
+        let rank = result.ranks();
+        assert_eq!(rank[&"D"] > rank[&"B"], true);
+        assert_eq!(rank[&"B"] > rank[&"A"], true);
+        assert!(result.converged());
+        assert!(result.residual() < 0.0000001);
+
+        println!("{:?}", rank);
+    }
+
+    #[test]
+    fn test_pagerank_out_degree() {
+        // A links to both B and C, which give all their rank back to A
+        let mut g = Graph::new();
         g.add_edge("A", "B", 0);
         g.add_edge("A", "C", 0);
-        g.add_edge("A", "D", 0);        
+        g.add_edge("B", "A", 0);
+        g.add_edge("C", "A", 0);
 
-        g.add_edge("B", "A", 0);        
-        g.add_edge("B", "D", 0);        
+        let rank = pagerank(&g, 0.85, 1e-10, 1000).unwrap().into_ranks();
+        let total: f64 = rank.values().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!((rank[&"B"] - rank[&"C"]).abs() < 1e-9);
+        // Stationary distribution: a = 0.05 + 0.85 (b + c), b = 0.05 + 0.85 a / 2
+        let a = (0.05 + 0.85 * 0.1) / (1.0 - 0.85 * 0.85);
+        assert!((rank[&"A"] - a).abs() < 1e-6);
+    }
 
-        g.add_edge("C", "D", 0);                
+    #[test]
+    fn test_pagerank_dangling() {
+        let mut g = Graph::new();
+        g.add_edge(1, 2, 0);
+        g.add_edge(1, 3, 0);
+        g.add_edge(2, 3, 0);
 
-        g.add_edge("D", "C", 0);                        
-        g.add_edge("D", "B", 0);                                
+        let rank = pagerank(&g, 0.85, 1e-10, 1000).unwrap().into_ranks();
+        let total: f64 = rank.values().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(rank[&3] > rank[&2]);
+        assert!(rank[&2] > rank[&1]);
+    }
 
+    #[test]
+    fn test_pagerank_max_iterations() {
+        let mut g = Graph::new();
+        g.add_edge(1, 2, 0);
+        g.add_edge(2, 1, 0);
+        g.add_edge(2, 3, 0);
 
-        let rank = pagerank(&g, 0.85, 0.0000001);
-        assert_eq!(rank[&"D"] > rank[&"B"], true );        
-        assert_eq!(rank[&"B"] > rank[&"A"], true );                
+        let result = pagerank(&g, 0.85, 0.0, 5).unwrap();
+        assert_eq!(5, result.iterations());
+        assert!(!result.converged());
 
-        println!("{:?}", rank);        
+        let result = pagerank(&g, 0.85, 1e-6, 1000).unwrap();
+        assert!(result.converged());
+        assert!(result.iterations() < 1000);
 
+        let empty: Graph<i32, i32> = Graph::new();
+        assert!(pagerank(&empty, 0.85, 1e-6, 10).unwrap().ranks().is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_personalized_pagerank() {
+        let mut g = Graph::new_undirected();
+        g.add_edge("A", "B", 0);
+        g.add_edge("B", "C", 0);
+        g.add_edge("C", "D", 0);
+
+        let uniform = pagerank(&g, 0.85, 1e-10, 1000).unwrap().into_ranks();
+        assert!((uniform[&"A"] - uniform[&"D"]).abs() < 1e-9);
+
+        let personalization: HashMap<_, _> = [("A", 1.0)].into_iter().collect();
+        let result = personalized_pagerank(&g, &personalization, 0.85, 1e-10, 1000).unwrap();
+        let rank = result.ranks();
+        assert!(rank[&"A"] > rank[&"D"]);
+        assert!(rank[&"B"] > rank[&"C"]);
+        let total: f64 = rank.values().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        let all_zero: HashMap<_, _> = [("A", 0.0)].into_iter().collect();
+        let result = personalized_pagerank(&g, &all_zero, 0.85, 1e-10, 1000).unwrap();
+        assert!((result.ranks()[&"A"] - uniform[&"A"]).abs() < 1e-9);
+
+        let unknown: HashMap<_, _> = [("Z", 1.0)].into_iter().collect();
+        assert_eq!(
+            Err(GraphError::MissingVertex("Z")),
+            personalized_pagerank(&g, &unknown, 0.85, 1e-10, 1000)
+        );
+    }
+}
@@ -1,13 +1,24 @@
-use super::{EdgeTypeTrait, Graph, GraphEdgeTrait, GraphVertexTrait};
+use super::single_path::check_endpoints;
+use super::{CsrGraph, EdgeTypeTrait, GraphEdgeTrait, GraphError, GraphStorage, GraphVertexTrait};
 use std::collections::{HashSet, VecDeque};
 
 /// Performs the Breadth First Search algorithm on the input graph
 /// Returns a Vec storing the vertices the were taken
-pub fn breadth_first_search<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
-    graph: &Graph<V, E, T>,
+/// Fails with Unreachable when target can't be reached from start
+pub fn breadth_first_search<V, E, G>(
+    graph: &G,
     start: V,
     target: V,
-) -> Option<Vec<V>> {
+) -> Result<Vec<V>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    check_endpoints(graph, &start, &target)?;
+    if let Some(csr) = graph.as_csr() {
+        return bfs_indices(csr, &start, &target).ok_or(GraphError::Unreachable(target));
+    }
     let mut visited = HashSet::new();
     let mut queue = VecDeque::new();
     let mut result = Vec::new();
@@ -16,44 +27,107 @@ pub fn breadth_first_search<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeT
         if visited.contains(&node) {
             continue;
         }
-        visited.insert(node);
+        visited.insert(node.clone());
+        for neighbor in graph.neighbours(&node) {
+            queue.push_back(neighbor.0.clone());
+        }
+
+        let found = node == target;
         result.push(node);
+        if found {
+            return Ok(result);
+        }
+    }
+    Err(GraphError::Unreachable(target))
+}
+
+/// breadth_first_search over the dense indices of a frozen graph
+fn bfs_indices<V, E, T>(graph: &CsrGraph<V, E, T>, start: &V, target: &V) -> Option<Vec<V>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    T: EdgeTypeTrait,
+{
+    let keys: Vec<&V> = graph.iter_vertices().collect();
+    let target = graph.index_of(target)?;
+    let mut visited = vec![false; keys.len()];
+    let mut queue = VecDeque::from([graph.index_of(start)?]);
+    let mut result = Vec::new();
+    while let Some(node) = queue.pop_front() {
+        if visited[node] {
+            continue;
+        }
+        visited[node] = true;
+        queue.extend(graph.neighbour_indices(node).0);
 
+        result.push(keys[node].clone());
         if node == target {
             return Some(result);
         }
-        if let Some(neighbors) = graph.adj_list().get(&node) {
-            for neighbor in neighbors {
-                queue.push_back(neighbor.0);
-            }
-        }
     }
     None
 }
 
 /// Performs the Depth First Search algorithm on the input graph
 /// Returns a Vec storing the vertices the were taken
-pub fn depth_first_search<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
-    graph: &Graph<V, E, T>,
+/// Fails with Unreachable when target can't be reached from start
+pub fn depth_first_search<V, E, G>(
+    graph: &G,
     start: V,
     target: V,
-) -> Option<Vec<V>> {
+) -> Result<Vec<V>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    check_endpoints(graph, &start, &target)?;
+    if let Some(csr) = graph.as_csr() {
+        return dfs_indices(csr, &start, &target).ok_or(GraphError::Unreachable(target));
+    }
     let mut visited = HashSet::new();
     let mut queue = VecDeque::new();
     let mut result = Vec::new();
     queue.push_back(start);
     while let Some(node) = queue.pop_front() {
+        if node == target {
+            result.push(node);
+            return Ok(result);
+        }
+        let neighbors: Vec<_> = graph.neighbours(&node).collect();
+        // Reverse the order, so we can still use VecDeque
+        for neighbor in neighbors.into_iter().rev() {
+            if visited.insert(neighbor.0.clone()) {
+                queue.push_front(neighbor.0.clone());
+            }
+        }
         result.push(node);
+    }
+    Err(GraphError::Unreachable(target))
+}
 
+/// depth_first_search over the dense indices of a frozen graph
+fn dfs_indices<V, E, T>(graph: &CsrGraph<V, E, T>, start: &V, target: &V) -> Option<Vec<V>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    T: EdgeTypeTrait,
+{
+    let keys: Vec<&V> = graph.iter_vertices().collect();
+    let target = graph.index_of(target)?;
+    let mut visited = vec![false; keys.len()];
+    let mut queue = VecDeque::from([graph.index_of(start)?]);
+    let mut result = Vec::new();
+    while let Some(node) = queue.pop_front() {
+        result.push(keys[node].clone());
         if node == target {
             return Some(result);
         }
-        if let Some(neighbors) = graph.adj_list().get(&node) {
-            // Reverse the order, so we can still use VecDeque
-            for neighbor in neighbors.iter().rev() {
-                if visited.insert(neighbor.0) {
-                    queue.push_front(neighbor.0);
-                }
+        // Reverse the order, so we can still use VecDeque
+        for &neighbor in graph.neighbour_indices(node).0.iter().rev() {
+            if !visited[neighbor] {
+                visited[neighbor] = true;
+                queue.push_front(neighbor);
             }
         }
     }
@@ -87,26 +161,24 @@ pub fn depth_first_search<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTra
 
 #[cfg(test)]
 mod test_search {
+    use crate::GraphError;
 
     #[test]
     fn test_bfs_find() {
         {
-            let mut graph = super::Graph::new();
+            let mut graph = crate::Graph::new();
             graph.add_vertex(1);
             graph.add_vertex(2);
             graph.add_vertex(3);
             graph.add_edge(1, 2, 0);
             graph.add_edge(2, 3, 0);
-            assert_eq!(super::breadth_first_search(&graph, 1, 3).is_none(), false);
+            assert_eq!(super::breadth_first_search(&graph, 1, 3).is_err(), false);
 
             let expected_path = vec![1, 2, 3];
-            assert_eq!(
-                super::breadth_first_search(&graph, 1, 3),
-                Some(expected_path)
-            );
+            assert_eq!(super::breadth_first_search(&graph, 1, 3), Ok(expected_path));
         }
         {
-            let mut graph = super::Graph::new();
+            let mut graph = crate::Graph::new();
             graph.add_vertex(1);
             graph.add_vertex(2);
             graph.add_vertex(3);
@@ -125,10 +197,10 @@ mod test_search {
 
             let result = super::breadth_first_search(&graph, 1, 7);
             let expected_path = vec![1, 2, 3, 4, 5, 6, 7];
-            assert_eq!(result, Some(expected_path));
+            assert_eq!(result, Ok(expected_path));
         }
         {
-            let mut g = super::Graph::new();
+            let mut g = crate::Graph::new();
             g.add_vertex("NYC");
             g.add_vertex("MTL");
             g.add_vertex("TOR");
@@ -137,42 +209,58 @@ mod test_search {
             g.add_edge("MTL", "TOR", 525);
             let result = super::breadth_first_search(&g, "NYC", "TOR");
             let expected_path = vec!["NYC", "MTL", "TOR"];
-            assert_eq!(result, Some(expected_path));
+            assert_eq!(result, Ok(expected_path));
         }
     }
 
     #[test]
     fn test_bfs_find_none() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_vertex(1);
         graph.add_vertex(2);
         graph.add_vertex(3);
         graph.add_edge(1, 2, 0);
         graph.add_edge(2, 3, 0);
-        assert_eq!(super::breadth_first_search(&graph, 1, 4).is_none(), true);
+        assert_eq!(
+            Err(GraphError::MissingVertex(4)),
+            super::breadth_first_search(&graph, 1, 4)
+        );
+        graph.add_vertex(4);
+        assert_eq!(
+            Err(GraphError::Unreachable(4)),
+            super::breadth_first_search(&graph, 1, 4)
+        );
     }
 
     #[test]
     fn test_dfs_find_none() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_vertex(1);
         graph.add_vertex(2);
         graph.add_vertex(3);
         graph.add_edge(1, 2, 0);
         graph.add_edge(2, 3, 0);
-        assert_eq!(super::depth_first_search(&graph, 1, 4).is_none(), true);
+        assert_eq!(
+            Err(GraphError::MissingVertex(4)),
+            super::depth_first_search(&graph, 1, 4)
+        );
+        graph.add_edge(4, 1, 0);
+        assert_eq!(
+            Err(GraphError::Unreachable(4)),
+            super::depth_first_search(&graph, 1, 4)
+        );
     }
 
     #[test]
     fn test_dfs_find() {
-        let mut graph1 = super::Graph::new();
+        let mut graph1 = crate::Graph::new();
         graph1.add_vertex(1);
         graph1.add_vertex(2);
         graph1.add_vertex(3);
         graph1.add_edge(1, 2, 0);
         graph1.add_edge(2, 3, 0);
-        assert_eq!(super::depth_first_search(&graph1, 1, 4).is_none(), true);
-        let mut graph2 = super::Graph::new();
+        assert_eq!(super::depth_first_search(&graph1, 1, 4).is_err(), true);
+        let mut graph2 = crate::Graph::new();
         graph2.add_vertex(1);
         graph2.add_vertex(2);
         graph2.add_vertex(3);
@@ -191,6 +279,6 @@ mod test_search {
 
         let result = super::depth_first_search(&graph2, 1, 7);
         let expected_path = vec![1, 2, 4, 5, 3, 6, 7];
-        assert_eq!(result, Some(expected_path));
+        assert_eq!(result, Ok(expected_path));
     }
 }
@@ -1,152 +1,1006 @@
-use super::{EdgeComparator, EdgeTypeTrait, Graph, GraphEdgeTrait, GraphVertexTrait};
+use super::{
+    CsrGraph, Directed, EdgeComparator, EdgeTypeTrait, GraphEdgeTrait, GraphError,
+    GraphPayloadTrait, GraphStorage, GraphVertexTrait, NegativeCycle,
+};
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
 use std::collections::HashMap;
+use std::collections::{HashSet, VecDeque};
 
-/// Shortest path algorithms.
+// Shortest path algorithms.
+
+/// Result of a single-source shortest path search.
+/// Holds the minimal distance to each vertex (`E::max_value()` when unreachable)
+/// and the predecessor of each reached vertex, so the paths can be rebuilt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShortestPaths<V: GraphVertexTrait, E: GraphEdgeTrait> {
+    source: V,
+    distances: HashMap<V, E>,
+    predecessors: HashMap<V, V>,
+}
+
+impl<V, E> ShortestPaths<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+    pub fn source(&self) -> V {
+        self.source.clone()
+    }
+
+    pub fn distances(&self) -> &HashMap<V, E> {
+        &self.distances
+    }
+
+    pub fn predecessors(&self) -> &HashMap<V, V> {
+        &self.predecessors
+    }
+
+    pub fn into_distances(self) -> HashMap<V, E> {
+        self.distances
+    }
+
+    /// Walks the predecessor map back from target to the source.
+    /// Returns None when target was not reached from the source.
+    pub fn path_to(&self, target: V) -> Option<Vec<V>> {
+        let mut path = vec![target.clone()];
+        let mut current = target;
+        while current != self.source {
+            current = self.predecessors.get(&current)?.clone();
+            // A predecessor chain longer than the graph means it doesn't lead to the source
+            if path.len() > self.distances.len() {
+                return None;
+            }
+            path.push(current.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+/// A single path between two vertices, along with its total cost
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<V: GraphVertexTrait, E: GraphEdgeTrait> {
+    vertices: Vec<V>,
+    cost: E,
+}
+
+impl<V, E> Path<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+    pub fn vertices(&self) -> &[V] {
+        &self.vertices
+    }
+
+    pub fn cost(&self) -> E {
+        self.cost
+    }
+
+    pub fn into_vertices(self) -> Vec<V> {
+        self.vertices
+    }
+}
 
 /// Dijkstra
 /// Performs edge relaxation
-/// The return could be a map/vec with the minimal value for each vertex, or
-/// it could be the total cost from start to end (if end is present)
-pub fn dijkstra<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
-    graph: &Graph<V, E, T>,
+/// Returns the minimal distance to each vertex, along with the predecessors
+/// needed to rebuild the shortest path to any of them.
+/// Only takes non-negative weights: every edge is checked before searching, and the first
+/// negative one fails with NegativeWeight. Fails with MissingVertex when start isn't in the graph
+pub fn dijkstra<V, E, G>(graph: &G, start: V) -> Result<ShortestPaths<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    dijkstra_with(graph, start, |_, _, value| *value)
+}
+
+/// Dijkstra over the weights the closure takes from the edge payloads
+pub fn dijkstra_by<V, E, W, G, F>(
+    graph: &G,
+    start: V,
+    weight: F,
+) -> Result<ShortestPaths<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&E) -> W,
+{
+    dijkstra_with(graph, start, |_, _, value| weight(value))
+}
+
+/// Dijkstra with the weight of each edge computed by the closure from (from, to, value)
+pub fn dijkstra_with<V, E, W, G, F>(
+    graph: &G,
+    start: V,
+    weight: F,
+) -> Result<ShortestPaths<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    if !graph.contains_vertex(&start) {
+        return Err(GraphError::MissingVertex(start));
+    }
+    non_negative_weights(graph, &weight)?;
+    Ok(dijkstra_unchecked(graph, start, weight))
+}
+
+/// Dijkstra without the up-front weight check, for callers that already know no weight
+/// is negative, such as Johnson's algorithm on its reweighted edges.
+/// Each reached vertex is expanded once, so each edge is weighed at most once
+pub(crate) fn dijkstra_unchecked<V, E, W, G, F>(
+    graph: &G,
     start: V,
-) -> Option<HashMap<V, E>> {
+    weight: F,
+) -> ShortestPaths<V, W>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    let frozen = graph
+        .as_csr()
+        .and_then(|csr| Some((csr, csr.index_of(&start)?)));
+    if let Some((csr, source)) = frozen {
+        return dijkstra_indices(csr, source, weight);
+    }
     let mut distances = HashMap::with_capacity(graph.vertices_count());
+    let mut predecessors = HashMap::new();
     let mut prio = BinaryHeap::new();
 
-    for vertex in graph.vertices() {
-        distances.insert(*vertex, E::max_value());
+    for vertex in graph.iter_vertices() {
+        distances.insert(vertex.clone(), W::max_value());
     }
 
-    prio.push(Reverse(EdgeComparator(start, start, E::default())));
-    distances.insert(start, E::default());
+    prio.push(Reverse(EdgeComparator(
+        start.clone(),
+        start.clone(),
+        W::default(),
+    )));
+    distances.insert(start.clone(), W::default());
 
     while let Some(Reverse(EdgeComparator(new, _, dist))) = prio.pop() {
-        for (next, weight) in graph.get_adjacent_vertices(new).unwrap() {
-            let next_distance = dist + *weight;
+        // A stale entry, the vertex was already expanded from a shorter distance
+        if dist > distances[&new] {
+            continue;
+        }
+        for (next, value) in graph.neighbours(&new) {
+            // An overflowing sum stays at max_value, so the edge is skipped
+            let next_distance = safe_add(dist, weight(&new, next, value));
             if next_distance < *distances.get(next).unwrap() {
-                distances.insert(*next, next_distance);
-                prio.push(Reverse(EdgeComparator(*next, new, next_distance)));
+                distances.insert(next.clone(), next_distance);
+                predecessors.insert(next.clone(), new.clone());
+                prio.push(Reverse(EdgeComparator(
+                    next.clone(),
+                    new.clone(),
+                    next_distance,
+                )));
+            }
+        }
+    }
+    ShortestPaths {
+        source: start,
+        distances,
+        predecessors,
+    }
+}
+
+/// dijkstra_unchecked over the dense indices of a frozen graph, keeping distances and
+/// predecessors in vectors; they only go into maps once the search is over
+fn dijkstra_indices<V, E, W, T, F>(
+    graph: &CsrGraph<V, E, T>,
+    source: usize,
+    weight: F,
+) -> ShortestPaths<V, W>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    T: EdgeTypeTrait,
+    F: Fn(&V, &V, &E) -> W,
+{
+    let keys: Vec<&V> = graph.iter_vertices().collect();
+    let mut distances = vec![W::max_value(); keys.len()];
+    let mut predecessors = vec![None; keys.len()];
+    let mut prio = BinaryHeap::new();
+
+    prio.push(Reverse(EdgeComparator(source, source, W::default())));
+    distances[source] = W::default();
+
+    while let Some(Reverse(EdgeComparator(new, _, dist))) = prio.pop() {
+        if dist > distances[new] {
+            continue;
+        }
+        let (targets, values) = graph.neighbour_indices(new);
+        for (&next, value) in targets.iter().zip(values) {
+            let next_distance = safe_add(dist, weight(keys[new], keys[next], value));
+            if next_distance < distances[next] {
+                distances[next] = next_distance;
+                predecessors[next] = Some(new);
+                prio.push(Reverse(EdgeComparator(next, new, next_distance)));
+            }
+        }
+    }
+    let mut by_key = HashMap::with_capacity(keys.len());
+    for (to, from) in keys.iter().zip(predecessors) {
+        if let Some(from) = from {
+            by_key.insert((*to).clone(), keys[from].clone());
+        }
+    }
+    ShortestPaths {
+        source: keys[source].clone(),
+        distances: keys
+            .iter()
+            .map(|key| (*key).clone())
+            .zip(distances)
+            .collect(),
+        predecessors: by_key,
+    }
+}
+
+/// Point-to-point Dijkstra, stopping as soon as target is settled.
+/// Unlike dijkstra, weights are checked lazily so the early exit pays off: only the edges
+/// the search relaxes are checked, the first negative one fails with NegativeWeight,
+/// and a negative edge the search never reaches goes unnoticed.
+/// Fails with MissingVertex when source or target isn't in the graph,
+/// and with Unreachable when there is no path between them
+pub fn shortest_path<V, E, G>(
+    graph: &G,
+    source: V,
+    target: V,
+) -> Result<Path<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    astar(graph, source, target, |_| E::default())
+}
+
+/// A*
+/// Point-to-point search guided by the heuristic, an estimate of the cost from a vertex to goal.
+/// The path found is the shortest one as long as the heuristic never overestimates;
+/// a zero heuristic makes it a plain Dijkstra. Negative weights are checked lazily,
+/// as in shortest_path: only on the edges relaxed before goal is settled.
+/// Fails the same way shortest_path does
+pub fn astar<V, E, G, H>(
+    graph: &G,
+    start: V,
+    goal: V,
+    heuristic: H,
+) -> Result<Path<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    H: Fn(&V) -> E,
+{
+    check_endpoints(graph, &start, &goal)?;
+    let mut search = Frontier::new(start);
+
+    while let Some((vertex, dist)) = search.pop() {
+        if vertex == goal {
+            let mut vertices = walk_back(&search.predecessors, goal);
+            vertices.reverse();
+            return Ok(Path {
+                vertices,
+                cost: dist,
+            });
+        }
+        for (next, value) in graph.neighbours(&vertex) {
+            if *value < E::default() {
+                return Err(GraphError::NegativeWeight(
+                    vertex.clone(),
+                    next.clone(),
+                    *value,
+                ));
+            }
+            let next_distance = safe_add(dist, *value);
+            search.relax_towards(
+                &vertex,
+                next,
+                next_distance,
+                safe_add(next_distance, heuristic(next)),
+            );
+        }
+    }
+    Err(GraphError::Unreachable(goal))
+}
+
+/// Bidirectional Dijkstra
+/// Searches forward from source and backward from target at the same time, always growing
+/// the side with the closest frontier, and stops once no meeting point can beat the best one.
+/// Negative weights are checked lazily, as in shortest_path, on the edges relaxed by either side.
+/// Fails the same way shortest_path does
+pub fn bidirectional_dijkstra<V, E, G>(
+    graph: &G,
+    source: V,
+    target: V,
+) -> Result<Path<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    check_endpoints(graph, &source, &target)?;
+    if source == target {
+        return Ok(Path {
+            vertices: vec![source],
+            cost: E::default(),
+        });
+    }
+    let mut forward = Frontier::new(source);
+    let mut backward = Frontier::new(target.clone());
+    // Cost of the best path found so far, and the vertex where both searches met on it
+    let mut best: Option<(E, V)> = None;
+
+    while let (Some(top_forward), Some(top_backward)) = (forward.top(), backward.top()) {
+        if best
+            .as_ref()
+            .is_some_and(|(cost, _)| safe_add(top_forward, top_backward) >= *cost)
+        {
+            break;
+        }
+        let is_forward = top_forward <= top_backward;
+        let (side, other) = if is_forward {
+            (&mut forward, &backward)
+        } else {
+            (&mut backward, &forward)
+        };
+        let Some((vertex, dist)) = side.pop() else {
+            break;
+        };
+
+        let outgoing = is_forward.then(|| graph.neighbours(&vertex));
+        let incoming = (!is_forward).then(|| graph.incoming(&vertex));
+        for (next, value) in outgoing
+            .into_iter()
+            .flatten()
+            .chain(incoming.into_iter().flatten())
+        {
+            if *value < E::default() {
+                let (from, to) = if is_forward {
+                    (vertex.clone(), next.clone())
+                } else {
+                    (next.clone(), vertex.clone())
+                };
+                return Err(GraphError::NegativeWeight(from, to, *value));
+            }
+            let next_distance = safe_add(dist, *value);
+            if !side.relax(&vertex, next, next_distance) {
+                continue;
+            }
+            if let Some(other_distance) = other.distances.get(next) {
+                let cost = safe_add(next_distance, *other_distance);
+                if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                    best = Some((cost, next.clone()));
+                }
+            }
+        }
+    }
+
+    match best {
+        Some((cost, meeting)) if cost < E::max_value() => {
+            let mut vertices = walk_back(&forward.predecessors, meeting.clone());
+            vertices.reverse();
+            vertices.extend(
+                walk_back(&backward.predecessors, meeting)
+                    .into_iter()
+                    .skip(1),
+            );
+            Ok(Path { vertices, cost })
+        }
+        _ => Err(GraphError::Unreachable(target)),
+    }
+}
+
+/// Yen's algorithm
+/// Up to k loopless paths from source to target, cheapest first. Every path after the first
+/// leaves a previous one at some vertex (the spur), and Dijkstra finds the way on from there
+/// without the vertices before it nor the edges the paths found so far take out of it.
+/// Fails the same way shortest_path does, and returns fewer than k paths when there aren't more
+pub fn k_shortest_paths<V, E, G>(
+    graph: &G,
+    source: V,
+    target: V,
+    k: usize,
+) -> Result<Vec<Path<V, E>>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    check_endpoints(graph, &source, &target)?;
+    let shortest = dijkstra(graph, source)?;
+    let Some(vertices) = shortest.path_to(target.clone()) else {
+        return Err(GraphError::Unreachable(target));
+    };
+    let mut found = vec![Path {
+        cost: shortest.distances()[&target],
+        vertices,
+    }];
+    let mut candidates: Vec<Path<V, E>> = Vec::new();
+
+    while found.len() < k {
+        let last = &found[found.len() - 1].vertices;
+        for i in 0..last.len() - 1 {
+            let (spur, root) = (&last[i], &last[..i]);
+            let detour = Detour {
+                graph,
+                spur: spur.clone(),
+                taken: found
+                    .iter()
+                    .filter(|path| path.vertices.get(..=i) == Some(&last[..=i]))
+                    .filter_map(|path| path.vertices.get(i + 1).cloned())
+                    .collect(),
+                removed: root.iter().cloned().collect(),
+            };
+
+            let Some(spur_path) = dijkstra(&detour, spur.clone())?.path_to(target.clone()) else {
+                continue;
+            };
+            let mut vertices = root.to_vec();
+            vertices.extend(spur_path);
+            if candidates
+                .iter()
+                .all(|candidate| candidate.vertices != vertices)
+            {
+                let cost = path_cost(graph, &vertices);
+                candidates.push(Path { vertices, cost });
             }
         }
+
+        let cheapest = candidates
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, candidate)| candidate.cost)
+            .map(|(i, _)| i);
+        match cheapest {
+            Some(i) => found.push(candidates.remove(i)),
+            None => break,
+        }
+    }
+    found.truncate(k);
+    Ok(found)
+}
+
+/// The graph Yen's algorithm searches from a spur: the vertices of the root path before it
+/// are gone, and so are the edges the paths found so far take out of the spur
+struct Detour<'a, G: GraphStorage> {
+    graph: &'a G,
+    spur: G::Vertex,
+    taken: HashSet<G::Vertex>,
+    removed: HashSet<G::Vertex>,
+}
+
+impl<G: GraphStorage> GraphStorage for Detour<'_, G> {
+    type Vertex = G::Vertex;
+    type Edge = G::Edge;
+    // Edges are only ever removed one way
+    type EdgeType = Directed;
+
+    fn vertices_count(&self) -> usize {
+        self.graph.vertices_count() - self.removed.len()
+    }
+
+    fn contains_vertex(&self, vertex: &G::Vertex) -> bool {
+        self.graph.contains_vertex(vertex) && !self.removed.contains(vertex)
+    }
+
+    fn iter_vertices(&self) -> impl Iterator<Item = &G::Vertex> {
+        self.graph
+            .iter_vertices()
+            .filter(|vertex| !self.removed.contains(*vertex))
+    }
+
+    fn neighbours(&self, vertex: &G::Vertex) -> impl Iterator<Item = (&G::Vertex, &G::Edge)> {
+        let is_spur = *vertex == self.spur;
+        let removed = self.removed.contains(vertex);
+        self.graph.neighbours(vertex).filter(move |(to, _)| {
+            !(removed || self.removed.contains(*to) || is_spur && self.taken.contains(*to))
+        })
+    }
+}
+
+/// Sum of the lightest edge between every two consecutive vertices
+fn path_cost<V, E, G>(graph: &G, vertices: &[V]) -> E
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    let mut total = E::default();
+    for pair in vertices.windows(2) {
+        total += graph
+            .neighbours(&pair[0])
+            .filter(|(to, _)| **to == pair[1])
+            .map(|(_, value)| *value)
+            .min()
+            .unwrap_or_default();
+    }
+    total
+}
+
+/// One side of a point-to-point search: tentative distances, the predecessor each of them
+/// came through, and a queue that may hold stale entries for already settled vertices.
+/// The queue is ordered by distance, or by the estimated total cost when searching with A*
+struct Frontier<V: GraphVertexTrait, E: GraphEdgeTrait> {
+    distances: HashMap<V, E>,
+    predecessors: HashMap<V, V>,
+    settled: HashSet<V>,
+    prio: BinaryHeap<Reverse<EdgeComparator<V, E>>>,
+}
+
+impl<V, E> Frontier<V, E>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+{
+    fn new(start: V) -> Self {
+        Frontier {
+            distances: HashMap::from([(start.clone(), E::default())]),
+            predecessors: HashMap::new(),
+            settled: HashSet::new(),
+            prio: BinaryHeap::from([Reverse(EdgeComparator(start.clone(), start, E::default()))]),
+        }
+    }
+
+    /// Priority of the closest vertex not settled yet
+    fn top(&mut self) -> Option<E> {
+        while let Some(Reverse(EdgeComparator(vertex, _, priority))) = self.prio.peek() {
+            if !self.settled.contains(vertex) {
+                return Some(*priority);
+            }
+            self.prio.pop();
+        }
+        None
+    }
+
+    /// Settles the closest vertex, along with its distance
+    fn pop(&mut self) -> Option<(V, E)> {
+        self.top()?;
+        let Reverse(EdgeComparator(vertex, _, _)) = self.prio.pop()?;
+        self.settled.insert(vertex.clone());
+        let dist = self.distances[&vertex];
+        Some((vertex, dist))
+    }
+
+    /// Reaches next through from, telling whether that improved its distance
+    fn relax(&mut self, from: &V, next: &V, next_distance: E) -> bool {
+        self.relax_towards(from, next, next_distance, next_distance)
+    }
+
+    /// Same as relax, queueing next by the given priority instead of its distance.
+    /// A settled vertex whose distance improves is opened again
+    fn relax_towards(&mut self, from: &V, next: &V, next_distance: E, priority: E) -> bool {
+        if next_distance == E::max_value()
+            || self
+                .distances
+                .get(next)
+                .is_some_and(|dist| next_distance >= *dist)
+        {
+            return false;
+        }
+        self.distances.insert(next.clone(), next_distance);
+        self.predecessors.insert(next.clone(), from.clone());
+        self.settled.remove(next);
+        self.prio.push(Reverse(EdgeComparator(
+            next.clone(),
+            from.clone(),
+            priority,
+        )));
+        true
+    }
+}
+
+/// Follows the predecessors from vertex until one without any, so the result ends at the start
+fn walk_back<V: GraphVertexTrait>(predecessors: &HashMap<V, V>, vertex: V) -> Vec<V> {
+    let mut path = vec![vertex];
+    while let Some(previous) = predecessors.get(path.last().unwrap()) {
+        path.push(previous.clone());
+    }
+    path
+}
+
+/// Checks that both ends of a search are in the graph, failing with the first one that isn't
+pub(crate) fn check_endpoints<V, E, G>(
+    graph: &G,
+    source: &V,
+    target: &V,
+) -> Result<(), GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V>,
+{
+    for vertex in [source, target] {
+        if !graph.contains_vertex(vertex) {
+            return Err(GraphError::MissingVertex(vertex.clone()));
+        }
     }
-    Some(distances)
+    Ok(())
+}
+
+/// Checks that every edge weight is non-negative, as Dijkstra needs,
+/// failing with the first edge that isn't
+pub(crate) fn non_negative_weights<V, E, W, G, F>(
+    graph: &G,
+    weight: &F,
+) -> Result<(), GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    for (from, to, value) in graph.iter_edges() {
+        let cost = weight(from, to, value);
+        if cost < W::default() {
+            return Err(GraphError::NegativeWeight(from.clone(), to.clone(), cost));
+        }
+    }
+    Ok(())
 }
 
 /// Bellman-Ford
 /// Performs edge relaxation, but, with a time complexity that is far away worst
 /// But, also, it is pretty good to find negative cycles
-pub fn bellman_ford<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
-    graph: &Graph<V, E, T>,
+/// Fails with the cycle itself when one is reachable from start,
+/// or with MissingVertex when start isn't in the graph
+pub fn bellman_ford<V, E, G>(graph: &G, start: V) -> Result<ShortestPaths<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    bellman_ford_with(graph, start, |_, _, value| *value)
+}
+
+/// Bellman-Ford over the weights the closure takes from the edge payloads
+pub fn bellman_ford_by<V, E, W, G, F>(
+    graph: &G,
     start: V,
-) -> Option<HashMap<V, E>> {
+    weight: F,
+) -> Result<ShortestPaths<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&E) -> W,
+{
+    bellman_ford_with(graph, start, |_, _, value| weight(value))
+}
+
+/// Bellman-Ford with the weight of each edge computed by the closure from (from, to, value)
+pub fn bellman_ford_with<V, E, W, G, F>(
+    graph: &G,
+    start: V,
+    weight: F,
+) -> Result<ShortestPaths<V, W>, GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    if !graph.contains_vertex(&start) {
+        return Err(GraphError::MissingVertex(start));
+    }
     let mut distances = HashMap::with_capacity(graph.vertices_count());
+    let mut predecessors = HashMap::new();
 
-    for vertex in graph.vertices() {
-        distances.insert(*vertex, E::max_value());
+    for vertex in graph.iter_vertices() {
+        distances.insert(vertex.clone(), W::max_value());
     }
 
-    distances.insert(start, E::default());
+    distances.insert(start.clone(), W::default());
+    relax_rounds(graph, &mut distances, &mut predecessors, &weight)?;
 
-    for _ in 0..graph.vertices_count() - 1 {
-        for (from, to, weight) in graph.edges() {
-            let mut next_distance = *distances.get(&from).unwrap();
-            next_distance = safe_add(next_distance, weight);
+    Ok(ShortestPaths {
+        source: start,
+        distances,
+        predecessors,
+    })
+}
+
+/// The Bellman-Ford rounds, relaxing every edge from the given distances until none improves.
+/// Starting every vertex at zero is the same as starting from an extra vertex linked to all
+/// of them, as Johnson's algorithm does. Fails with the cycle itself when there is a negative one
+pub(crate) fn relax_rounds<V, E, W, G, F>(
+    graph: &G,
+    distances: &mut HashMap<V, W>,
+    predecessors: &mut HashMap<V, V>,
+    weight: &F,
+) -> Result<(), GraphError<V, W>>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    // |V| - 1 rounds are enough to settle every distance, so anything
+    // still being relaxed on round |V| is caused by a negative cycle
+    let mut last_relaxed = None;
+    for _ in 0..graph.vertices_count() {
+        last_relaxed = None;
+        for (from, to, value) in graph.iter_edges() {
+            let mut next_distance = *distances.get(from).unwrap();
+            next_distance = safe_add(next_distance, weight(from, to, value));
 
-            if next_distance < *distances.get(&to).unwrap() {
-                distances.insert(to, next_distance);
+            if next_distance < *distances.get(to).unwrap() {
+                distances.insert(to.clone(), next_distance);
+                predecessors.insert(to.clone(), from.clone());
+                last_relaxed = Some(to.clone());
             }
         }
+        if last_relaxed.is_none() {
+            break;
+        }
     }
 
-    for (from, to, weight) in graph.edges() {
-        let mut dist = *distances.get(&from).unwrap();
-        dist = safe_add(dist, weight);
-        if dist < *distances.get(&to).unwrap() {
-            return None;
-        }
+    match last_relaxed {
+        Some(vertex) => Err(negative_cycle(graph, predecessors, vertex, weight).into()),
+        None => Ok(()),
+    }
+}
+
+/// Single-source shortest paths with the algorithm picked from the edge values:
+/// BFS when they are all the same (hops times that value), Dijkstra when none is negative,
+/// Bellman-Ford otherwise. Fails the same way the picked algorithm does.
+pub fn shortest_paths<V, E, G>(graph: &G, start: V) -> Result<ShortestPaths<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    let mut values = graph.iter_edges().map(|(_, _, value)| *value);
+    let first = values.next().unwrap_or_default();
+    let (mut uniform, mut negative) = (true, first < E::default());
+    for value in values {
+        uniform &= value == first;
+        negative |= value < E::default();
     }
 
-    Some(distances)
+    if negative {
+        bellman_ford(graph, start)
+    } else if uniform {
+        uniform_bfs(graph, start, first)
+    } else {
+        dijkstra(graph, start)
+    }
 }
 
-fn safe_add<E: GraphEdgeTrait>(next_distance: E, weight: E) -> E {
-    let res = next_distance.checked_add(&weight);
-    match res {
-        Some(sum) => {
-            if next_distance == E::max_value() {
-                weight
-            } else {
-                sum
+/// BFS standing in for Dijkstra when every edge is worth the same non-negative value
+fn uniform_bfs<V, E, G>(
+    graph: &G,
+    start: V,
+    value: E,
+) -> Result<ShortestPaths<V, E>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
+    if !graph.contains_vertex(&start) {
+        return Err(GraphError::MissingVertex(start));
+    }
+    let mut distances: HashMap<V, E> = graph
+        .iter_vertices()
+        .map(|vertex| (vertex.clone(), E::max_value()))
+        .collect();
+    let mut predecessors = HashMap::new();
+    let mut reached = HashSet::from([&start]);
+    let mut queue = VecDeque::from([&start]);
+    distances.insert(start.clone(), E::default());
+
+    while let Some(vertex) = queue.pop_front() {
+        let next_distance = safe_add(distances[vertex], value);
+        for (next, _) in graph.neighbours(vertex) {
+            if reached.insert(next) {
+                distances.insert(next.clone(), next_distance);
+                predecessors.insert(next.clone(), vertex.clone());
+                queue.push_back(next);
             }
         }
-        None => E::max_value(),
     }
+
+    Ok(ShortestPaths {
+        source: start,
+        distances,
+        predecessors,
+    })
+}
+
+/// Rebuilds the negative cycle from the predecessors left by Bellman-Ford.
+/// Walking back |V| times from a vertex relaxed on the last round is
+/// guaranteed to land on the cycle, then we follow it until it closes.
+fn negative_cycle<V, E, W, G, F>(
+    graph: &G,
+    predecessors: &HashMap<V, V>,
+    relaxed: V,
+    weight: &F,
+) -> NegativeCycle<V, W>
+where
+    V: GraphVertexTrait,
+    E: GraphPayloadTrait,
+    W: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+    F: Fn(&V, &V, &E) -> W,
+{
+    let mut start = relaxed;
+    for _ in 0..graph.vertices_count() {
+        start = predecessors[&start].clone();
+    }
+
+    let mut cycle = vec![start.clone()];
+    let mut current = predecessors[&start].clone();
+    while current != start {
+        let next = predecessors[&current].clone();
+        cycle.push(current);
+        current = next;
+    }
+    cycle.reverse();
+
+    let mut total = W::default();
+    for (i, from) in cycle.iter().enumerate() {
+        let to = &cycle[(i + 1) % cycle.len()];
+        // Parallel edges: the lightest one is the one that closes the cycle
+        let lightest = graph
+            .neighbours(from)
+            .filter(|(next, _)| *next == to)
+            .map(|(next, value)| weight(from, next, value))
+            .min()
+            .unwrap_or_default();
+        total += lightest;
+    }
+
+    NegativeCycle::new(cycle, total)
+}
+
+fn safe_add<E: GraphEdgeTrait>(next_distance: E, weight: E) -> E {
+    if next_distance == E::max_value() {
+        // Nothing can be reached through a vertex that wasn't reached yet
+        return E::max_value();
+    }
+    next_distance
+        .checked_add(&weight)
+        .unwrap_or_else(E::max_value)
 }
 
 #[cfg(test)]
 mod test_single_path {
+    use super::astar;
     use super::bellman_ford;
+    use super::bellman_ford_by;
+    use super::bellman_ford_with;
+    use super::bidirectional_dijkstra;
     use super::dijkstra;
-    use ntest::timeout;
-    use std::collections::HashMap;
+    use super::dijkstra_by;
+    use super::dijkstra_with;
+    use super::k_shortest_paths;
+    use super::shortest_path;
+    use super::shortest_paths;
+    use crate::GraphError;
+    use std::collections::{HashMap, HashSet};
 
     #[test]
     fn test_single_graph_dijkstra() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_edge(0, 1, 2);
 
         let dists_0: HashMap<_, _> = vec![(0, 0), (1, 2)].into_iter().collect();
 
-        assert_eq!(dijkstra(&graph, 0), Some(dists_0));
+        assert_eq!(dijkstra(&graph, 0).unwrap().distances(), &dists_0);
 
         let dists_1: HashMap<_, _> = vec![(1, 0), (0, i32::max_value())].into_iter().collect();
 
-        assert_eq!(dijkstra(&graph, 1), Some(dists_1));
+        assert_eq!(dijkstra(&graph, 1).unwrap().distances(), &dists_1);
     }
 
     #[test]
     fn test_single_graph_bellman() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_edge(0, 1, 2);
 
         let dists_0: HashMap<_, _> = vec![(0, 0), (1, 2)].into_iter().collect();
 
-        assert_eq!(bellman_ford(&graph, 0), Some(dists_0));
+        assert_eq!(bellman_ford(&graph, 0).unwrap().distances(), &dists_0);
 
         let dists_1: HashMap<_, _> = vec![(1, 0), (0, i32::max_value())].into_iter().collect();
 
-        assert_eq!(bellman_ford(&graph, 1), Some(dists_1));
+        assert_eq!(bellman_ford(&graph, 1).unwrap().distances(), &dists_1);
     }
 
     #[test]
-    #[timeout(3000)]
-    #[should_panic]
     fn test_negative_cycle() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_edge(1, 2, 1);
         graph.add_edge(2, 3, 3);
         graph.add_edge(3, 4, 2);
         graph.add_edge(4, 2, -6);
 
-        dijkstra(&graph, 0);
+        assert_eq!(
+            Err(GraphError::NegativeWeight(4, 2, -6)),
+            dijkstra(&graph, 1)
+        );
+        assert_eq!(
+            Err(GraphError::NegativeWeight(4, 2, -6)),
+            dijkstra_with(&graph, 1, |_, _, value| *value)
+        );
+        assert!(dijkstra_with(&graph, 1, |_, _, value: &i32| value.abs()).is_ok());
     }
 
     #[test]
     fn test_detect_negative_cycle() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_edge(1, 2, 1);
         graph.add_edge(2, 3, 3);
         graph.add_edge(3, 4, 2);
         graph.add_edge(4, 2, -6);
-        assert_eq!(None, bellman_ford(&graph, 0));
+        let GraphError::NegativeCycle(cycle) = bellman_ford(&graph, 1).unwrap_err() else {
+            panic!("expected a negative cycle")
+        };
+        assert_eq!(-1, cycle.weight());
+        assert_eq!(3, cycle.cycle().len());
+        assert!(cycle.contains(2) && cycle.contains(3) && cycle.contains(4));
     }
 
     #[test]
-    fn test_cities_graph() {
-        let mut graph = super::Graph::new_undirected();
+    fn test_negative_cycle_not_reachable() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge(0, 1, 2);
+        graph.add_edge(1, 2, 3);
+        graph.add_edge(2, 1, -6);
+        graph.add_edge(4, 5, -1);
+
+        assert!(bellman_ford(&graph, 0).is_err());
+
+        let res = bellman_ford(&graph, 4).unwrap();
+        assert_eq!(Some(&-1), res.distances().get(&5));
+        assert_eq!(Some(&i32::MAX), res.distances().get(&1));
+        assert_eq!(Some(&i32::MAX), res.distances().get(&2));
+    }
+
+    #[test]
+    fn test_arbitrage_cycle() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge("USD", "EUR", 2);
+        graph.add_edge("EUR", "GBP", -3);
+        graph.add_edge("GBP", "USD", -1);
+        graph.add_edge("GBP", "JPY", 5);
+
+        let cycle = bellman_ford(&graph, "JPY").unwrap();
+        assert_eq!(None, cycle.path_to("USD"));
+
+        let GraphError::NegativeCycle(cycle) = bellman_ford(&graph, "USD").unwrap_err() else {
+            panic!("expected a negative cycle")
+        };
+        assert_eq!(-2, cycle.weight());
+        let mut vertices = cycle.cycle().to_vec();
+        vertices.sort();
+        assert_eq!(vec!["EUR", "GBP", "USD"], vertices);
+        // Consecutive vertices follow the direction of the edges
+        let first = cycle.cycle().iter().position(|v| *v == "USD").unwrap();
+        assert_eq!("EUR", cycle.cycle()[(first + 1) % 3]);
+    }
+
+    fn cities() -> crate::Graph<&'static str, i32, crate::Undirected> {
+        let mut graph = crate::Graph::new_undirected();
         graph.add_edge("New York", "Pittsburgh", 400);
         graph.add_edge("New York", "Philadelphia", 100);
         graph.add_edge("Pittsburgh", "Indianapolis", 400);
@@ -187,27 +1041,32 @@ mod test_single_path {
         graph.add_edge("Miami", "Tampa", 280);
         graph.add_edge("Miami", "Orlando", 230);
         graph.add_edge("Miami", "Jacksonville", 350);
+        graph
+    }
 
+    #[test]
+    fn test_cities_graph() {
+        let graph = cities();
         let nyc = dijkstra(&graph, "New York").unwrap();
         println!("{:?}", nyc);
-        assert_eq!(Some(&1290), nyc.get("Miami"));
-        assert_eq!(Some(&0), nyc.get("New York"));
+        assert_eq!(Some(&1290), nyc.distances().get("Miami"));
+        assert_eq!(Some(&0), nyc.distances().get("New York"));
 
         let denver = dijkstra(&graph, "Denver").unwrap();
         println!("{:?}", denver);
-        assert_eq!(Some(&750), denver.get("Las Vegas"));
-        assert_eq!(Some(&0), denver.get("Denver"));
+        assert_eq!(Some(&750), denver.distances().get("Las Vegas"));
+        assert_eq!(Some(&0), denver.distances().get("Denver"));
 
         let pits = dijkstra(&graph, "Pittsburgh").unwrap();
         println!("{:?}", pits);
-        assert_eq!(Some(&400), pits.get("New York"));
-        assert_ne!(Some(&775), pits.get("Washington, D.C."));
-        assert_eq!(Some(&640), pits.get("Washington, D.C."));
+        assert_eq!(Some(&400), pits.distances().get("New York"));
+        assert_ne!(Some(&775), pits.distances().get("Washington, D.C."));
+        assert_eq!(Some(&640), pits.distances().get("Washington, D.C."));
     }
 
     #[test]
     fn test_dijkstra_and_bellman_same_result() {
-        let mut graph = super::Graph::new_undirected();
+        let mut graph = crate::Graph::new_undirected();
         graph.add_edge(0, 1, 4);
         graph.add_edge(0, 2, 1);
         graph.add_edge(2, 1, 2);
@@ -224,54 +1083,57 @@ mod test_single_path {
             .collect();
 
         let res_0 = dijkstra(&graph, 0);
-        assert_eq!(hashmap_0, res_0.unwrap());
+        assert_eq!(hashmap_0, *res_0.unwrap().distances());
         let res_1 = dijkstra(&graph, 1);
-        assert_eq!(hashmap_1, res_1.unwrap());
+        assert_eq!(hashmap_1, *res_1.unwrap().distances());
 
         let res1 = bellman_ford(&graph, 0);
-        assert_eq!(hashmap_0, res1.unwrap());
+        assert_eq!(hashmap_0, *res1.unwrap().distances());
         let res2 = bellman_ford(&graph, 1);
-        assert_eq!(hashmap_1, res2.unwrap());
+        assert_eq!(hashmap_1, *res2.unwrap().distances());
     }
 
     #[test]
     fn test_bellman() {
-        let mut h = super::Graph::new();
+        let mut h = crate::Graph::new();
         h.add_edge(0, 1, 4);
         h.add_edge(0, 2, 2);
         h.add_edge(0, 3, 1);
         h.add_edge(1, 2, 1);
         h.add_edge(1, 3, 1);
-        assert_eq!(bellman_ford(&h, 0).unwrap().get(&1).unwrap(), &4);
+        assert_eq!(
+            bellman_ford(&h, 0).unwrap().distances().get(&1).unwrap(),
+            &4
+        );
 
         assert_eq!(
-            *bellman_ford(&h, 1).unwrap().get(&0).unwrap(),
+            *bellman_ford(&h, 1).unwrap().distances().get(&0).unwrap(),
             i32::max_value()
         );
 
         assert_eq!(
-            *bellman_ford(&h, 2).unwrap().get(&1).unwrap(),
+            *bellman_ford(&h, 2).unwrap().distances().get(&1).unwrap(),
             i32::max_value()
         );
         assert_eq!(
-            *bellman_ford(&h, 2).unwrap().get(&3).unwrap(),
+            *bellman_ford(&h, 2).unwrap().distances().get(&3).unwrap(),
             i32::max_value()
         );
         assert_eq!(
-            *bellman_ford(&h, 2).unwrap().get(&0).unwrap(),
+            *bellman_ford(&h, 2).unwrap().distances().get(&0).unwrap(),
             i32::max_value()
         );
 
         assert_eq!(
-            *bellman_ford(&h, 3).unwrap().get(&1).unwrap(),
+            *bellman_ford(&h, 3).unwrap().distances().get(&1).unwrap(),
             i32::max_value()
         );
         assert_eq!(
-            *bellman_ford(&h, 3).unwrap().get(&2).unwrap(),
+            *bellman_ford(&h, 3).unwrap().distances().get(&2).unwrap(),
             i32::max_value()
         );
         assert_eq!(
-            *bellman_ford(&h, 3).unwrap().get(&0).unwrap(),
+            *bellman_ford(&h, 3).unwrap().distances().get(&0).unwrap(),
             i32::max_value()
         );
 
@@ -280,4 +1142,452 @@ mod test_single_path {
         println!("{:?}", bellman_ford(&h, 2));
         println!("{:?}", bellman_ford(&h, 3));
     }
+
+    #[test]
+    fn test_cities_graph_path() {
+        let mut graph = crate::Graph::new_undirected();
+        graph.add_edge("New York", "Pittsburgh", 400);
+        graph.add_edge("New York", "Philadelphia", 100);
+        graph.add_edge("Pittsburgh", "Columbus", 185);
+        graph.add_edge("Philadelphia", "Columbus", 450);
+        graph.add_edge("Philadelphia", "Washington, D.C.", 140);
+        graph.add_edge("Washington, D.C.", "Charlotte", 400);
+        graph.add_edge("Charlotte", "Miami", 650);
+        graph.add_vertex("Honolulu");
+
+        let nyc = dijkstra(&graph, "New York").unwrap();
+        assert_eq!(
+            Some(vec![
+                "New York",
+                "Philadelphia",
+                "Washington, D.C.",
+                "Charlotte",
+                "Miami"
+            ]),
+            nyc.path_to("Miami")
+        );
+        assert_eq!(Some(vec!["New York"]), nyc.path_to("New York"));
+        assert_eq!(None, nyc.path_to("Honolulu"));
+
+        let pits = bellman_ford(&graph, "Pittsburgh").unwrap();
+        assert_eq!(
+            Some(vec!["Pittsburgh", "New York", "Philadelphia"]),
+            pits.path_to("Philadelphia")
+        );
+    }
+
+    #[test]
+    fn test_dijkstra_and_bellman_same_path() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge(0, 1, 4);
+        graph.add_edge(0, 2, 1);
+        graph.add_edge(2, 1, 2);
+        graph.add_edge(2, 3, 5);
+        graph.add_edge(1, 3, 1);
+        graph.add_edge(3, 4, 3);
+
+        let res_dijkstra = dijkstra(&graph, 0).unwrap();
+        let res_bellman = bellman_ford(&graph, 0).unwrap();
+        assert_eq!(Some(vec![0, 2, 1, 3, 4]), res_dijkstra.path_to(4));
+        assert_eq!(res_dijkstra.path_to(4), res_bellman.path_to(4));
+        assert_eq!(None, res_bellman.path_to(5));
+        assert_eq!(None, dijkstra(&graph, 4).unwrap().path_to(0));
+    }
+
+    #[test]
+    fn test_string_vertices() {
+        let mut graph = crate::Graph::new();
+        // Owned keys, as read from a file at runtime
+        let lines = "A B 4\nA C 1\nC B 2\nB D 5";
+        for line in lines.lines() {
+            let fields: Vec<&str> = line.split(' ').collect();
+            let weight: i32 = fields[2].parse().unwrap();
+            graph.add_edge(fields[0].to_string(), fields[1].to_string(), weight);
+        }
+
+        let paths = dijkstra(&graph, "A".to_string()).unwrap();
+        assert_eq!(Some(&8), paths.distances().get("D"));
+        let path = paths.path_to("D".to_string()).unwrap();
+        assert_eq!(vec!["A", "C", "B", "D"], path);
+        assert_eq!(paths, bellman_ford(&graph, "A".to_string()).unwrap());
+    }
+
+    #[test]
+    fn test_edge_payloads() {
+        #[derive(Debug, Clone, PartialEq)]
+        struct Road {
+            km: u32,
+            minutes: u32,
+        }
+
+        let mut graph = crate::Graph::new();
+        graph.add_edge("A", "B", Road { km: 10, minutes: 5 });
+        graph.add_edge("B", "D", Road { km: 10, minutes: 5 });
+        graph.add_edge("A", "C", Road { km: 4, minutes: 9 });
+        graph.add_edge("C", "D", Road { km: 4, minutes: 9 });
+
+        let shortest = dijkstra_by(&graph, "A", |road| road.km).unwrap();
+        assert_eq!(Some(&8), shortest.distances().get("D"));
+        assert_eq!(Some(vec!["A", "C", "D"]), shortest.path_to("D"));
+
+        let fastest = bellman_ford_by(&graph, "A", |road| road.minutes).unwrap();
+        assert_eq!(Some(&10), fastest.distances().get("D"));
+        assert_eq!(Some(vec!["A", "B", "D"]), fastest.path_to("D"));
+    }
+
+    #[test]
+    fn test_weight_closure() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge("A", "B", 2);
+        graph.add_edge("B", "D", 2);
+        graph.add_edge("A", "C", 3);
+        graph.add_edge("C", "D", 3);
+
+        // Going through the B toll booth costs 5 on top of the distance
+        let toll = |_: &&str, to: &&str, km: &i32| if *to == "B" { km + 5 } else { *km };
+        let paths = dijkstra_with(&graph, "A", toll).unwrap();
+        assert_eq!(Some(&6), paths.distances().get("D"));
+        assert_eq!(Some(vec!["A", "C", "D"]), paths.path_to("D"));
+        assert_eq!(paths, bellman_ford_with(&graph, "A", toll).unwrap());
+        assert_eq!(
+            Some(&4),
+            dijkstra(&graph, "A").unwrap().distances().get("D")
+        );
+    }
+
+    #[test]
+    fn test_dijkstra_overflow() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge(0, 1, i32::MAX - 5);
+        graph.add_edge(1, 2, 10);
+        graph.add_edge(0, 2, 7);
+        graph.add_edge(2, 3, i32::MAX);
+
+        let res = dijkstra(&graph, 0).unwrap();
+        assert_eq!(Some(&7), res.distances().get(&2));
+        assert_eq!(Some(vec![0, 2]), res.path_to(2));
+        // 7 + i32::MAX doesn't fit, so 3 is left unreached
+        assert_eq!(Some(&i32::MAX), res.distances().get(&3));
+        assert_eq!(None, res.path_to(3));
+    }
+
+    #[test]
+    fn test_missing_start() {
+        let mut graph: crate::Graph<i32, i32> = crate::Graph::new();
+        assert_eq!(Err(GraphError::MissingVertex(0)), bellman_ford(&graph, 0));
+        graph.add_edge(1, 2, 3);
+        assert_eq!(Err(GraphError::MissingVertex(0)), dijkstra(&graph, 0));
+        assert_eq!(Err(GraphError::MissingVertex(0)), bellman_ford(&graph, 0));
+    }
+
+    #[test]
+    fn test_shortest_paths_picks_algorithm() {
+        let mut graph = crate::Graph::new();
+        for (from, to) in [(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)] {
+            graph.add_edge(from, to, 5);
+        }
+        // Every edge is worth 5, so this is a BFS
+        let uniform = shortest_paths(&graph, 0).unwrap();
+        assert_eq!(Some(&15), uniform.distances().get(&4));
+        assert_eq!(Some(vec![0, 1, 3, 4]), uniform.path_to(4));
+        assert_eq!(
+            Some(&i32::MAX),
+            shortest_paths(&graph, 4).unwrap().distances().get(&0)
+        );
+        assert_eq!(
+            dijkstra(&graph, 0).unwrap().distances(),
+            uniform.distances()
+        );
+
+        graph.update_edge_weight(0, 1, 20);
+        let weighted = shortest_paths(&graph, 0).unwrap();
+        assert_eq!(Some(vec![0, 2, 3, 4]), weighted.path_to(4));
+        assert_eq!(dijkstra(&graph, 0).unwrap(), weighted);
+
+        graph.update_edge_weight(1, 3, -30);
+        let negative = shortest_paths(&graph, 0).unwrap();
+        assert_eq!(Some(&-5), negative.distances().get(&4));
+        assert_eq!(bellman_ford(&graph, 0).unwrap(), negative);
+        assert!(dijkstra(&graph, 0).is_err());
+
+        graph.add_edge(3, 1, 10);
+        assert!(matches!(
+            shortest_paths(&graph, 0),
+            Err(GraphError::NegativeCycle(_))
+        ));
+        assert_eq!(Err(GraphError::MissingVertex(9)), shortest_paths(&graph, 9));
+    }
+
+    #[test]
+    fn test_point_to_point_cities() {
+        let graph = cities();
+        for (source, target) in [
+            ("New York", "Miami"),
+            ("Denver", "Las Vegas"),
+            ("Pittsburgh", "Washington, D.C."),
+            ("Los Angeles", "Jacksonville"),
+            ("Houston", "Chicago"),
+        ] {
+            let all = dijkstra(&graph, source).unwrap();
+            let single = shortest_path(&graph, source, target).unwrap();
+            let both = bidirectional_dijkstra(&graph, source, target).unwrap();
+            assert_eq!(all.distances()[target], single.cost());
+            assert_eq!(all.distances()[target], both.cost());
+            assert_eq!(Some(single.vertices().to_vec()), all.path_to(target));
+            assert_eq!(single, both);
+        }
+        let route = shortest_path(&graph, "New York", "Miami").unwrap();
+        assert_eq!(1290, route.cost());
+        assert_eq!(
+            vec![
+                "New York",
+                "Philadelphia",
+                "Washington, D.C.",
+                "Charlotte",
+                "Miami"
+            ],
+            route.into_vertices()
+        );
+    }
+
+    #[test]
+    fn test_point_to_point_directed() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge(0, 1, 7);
+        graph.add_edge(0, 2, 9);
+        graph.add_edge(0, 5, 14);
+        graph.add_edge(1, 2, 10);
+        graph.add_edge(1, 3, 15);
+        graph.add_edge(2, 3, 11);
+        graph.add_edge(2, 5, 2);
+        graph.add_edge(3, 4, 6);
+        graph.add_edge(5, 4, 9);
+        graph.add_vertex(6);
+
+        for target in 0..6 {
+            let expected = dijkstra(&graph, 0).unwrap().distances()[&target];
+            assert_eq!(expected, shortest_path(&graph, 0, target).unwrap().cost());
+            assert_eq!(
+                expected,
+                bidirectional_dijkstra(&graph, 0, target).unwrap().cost()
+            );
+        }
+        let path = bidirectional_dijkstra(&graph, 0, 4).unwrap();
+        assert_eq!(&[0, 2, 5, 4], path.vertices());
+        assert_eq!(20, path.cost());
+        assert_eq!(&[3], shortest_path(&graph, 3, 3).unwrap().vertices());
+        assert_eq!(0, bidirectional_dijkstra(&graph, 3, 3).unwrap().cost());
+
+        // Edges only go one way, and 6 has none at all
+        assert_eq!(Err(GraphError::Unreachable(0)), shortest_path(&graph, 4, 0));
+        assert_eq!(
+            Err(GraphError::Unreachable(0)),
+            bidirectional_dijkstra(&graph, 4, 0)
+        );
+        assert_eq!(
+            Err(GraphError::Unreachable(6)),
+            bidirectional_dijkstra(&graph, 0, 6)
+        );
+        assert_eq!(
+            Err(GraphError::MissingVertex(9)),
+            shortest_path(&graph, 0, 9)
+        );
+        assert_eq!(
+            Err(GraphError::MissingVertex(9)),
+            bidirectional_dijkstra(&graph, 9, 0)
+        );
+
+        // The check is lazy: edges never reached don't matter, the ones met on the way
+        // fail the search, while dijkstra rejects any of them up front
+        graph.add_edge(6, 0, -1);
+        graph.add_edge(8, 7, -2);
+        assert_eq!(
+            Err(GraphError::NegativeWeight(6, 0, -1)),
+            dijkstra(&graph, 0)
+        );
+        assert_eq!(20, shortest_path(&graph, 0, 4).unwrap().cost());
+        assert_eq!(20, bidirectional_dijkstra(&graph, 0, 4).unwrap().cost());
+        graph.add_edge(5, 4, -3);
+        assert_eq!(
+            Err(GraphError::NegativeWeight(5, 4, -3)),
+            shortest_path(&graph, 0, 4)
+        );
+        assert_eq!(
+            Err(GraphError::NegativeWeight(5, 4, -3)),
+            bidirectional_dijkstra(&graph, 0, 4)
+        );
+    }
+
+    #[test]
+    fn test_astar_zero_heuristic() {
+        let graph = cities();
+        for source in ["New York", "Denver", "Miami", "Los Angeles"] {
+            let all = dijkstra(&graph, source).unwrap();
+            for &target in graph.vertices() {
+                let path = astar(&graph, source, target, |_| 0).unwrap();
+                assert_eq!(all.distances()[target], path.cost());
+                assert_eq!(Some(path.into_vertices()), all.path_to(target));
+            }
+        }
+    }
+
+    #[test]
+    fn test_astar_grid() {
+        // 6x6 grid where moving right costs 1 and moving down costs 2,
+        // with a wall on column 2 that is only open on the last row
+        let mut graph = crate::Graph::new_undirected();
+        for x in 0..6 {
+            for y in 0..6 {
+                if x < 5 && (x != 1 || y == 5) {
+                    graph.add_edge((x, y), (x + 1, y), 1);
+                }
+                if y < 5 {
+                    graph.add_edge((x, y), (x, y + 1), 2);
+                }
+            }
+        }
+        let goal = (5, 0);
+        let manhattan = |&(x, y): &(i32, i32)| (goal.0 - x).abs() + 2 * (goal.1 - y).abs();
+
+        let path = astar(&graph, (0, 0), goal, manhattan).unwrap();
+        assert_eq!(
+            dijkstra(&graph, (0, 0)).unwrap().distances()[&goal],
+            path.cost()
+        );
+        assert_eq!(25, path.cost());
+        // Whichever way it goes down, it has to go through the gap in the wall
+        let gap = path.vertices().iter().position(|vertex| *vertex == (1, 5));
+        assert_eq!(Some(&(2, 5)), gap.and_then(|i| path.vertices().get(i + 1)));
+    }
+
+    #[test]
+    fn test_astar_reopens_vertices() {
+        // The heuristic never overestimates, but it makes "c" look closer through "b",
+        // so "c" has to be settled again once the cheaper way through "a" shows up
+        let mut graph = crate::Graph::new();
+        graph.add_edge("s", "a", 1);
+        graph.add_edge("s", "b", 1);
+        graph.add_edge("a", "c", 1);
+        graph.add_edge("b", "c", 2);
+        graph.add_edge("c", "g", 10);
+        let heuristic = |vertex: &&str| if *vertex == "a" { 11 } else { 0 };
+
+        let path = astar(&graph, "s", "g", heuristic).unwrap();
+        assert_eq!(12, path.cost());
+        assert_eq!(&["s", "a", "c", "g"], path.vertices());
+
+        assert_eq!(
+            Err(GraphError::Unreachable("s")),
+            astar(&graph, "g", "s", heuristic)
+        );
+        assert_eq!(
+            Err(GraphError::MissingVertex("x")),
+            astar(&graph, "x", "g", heuristic)
+        );
+        // A negative edge is only noticed once the search relaxes it
+        graph.add_edge("x", "s", -1);
+        assert_eq!(12, astar(&graph, "s", "g", heuristic).unwrap().cost());
+        assert!(dijkstra(&graph, "s").is_err());
+        graph.add_edge("c", "g", -1);
+        assert_eq!(
+            Err(GraphError::NegativeWeight("c", "g", -1)),
+            astar(&graph, "s", "g", heuristic)
+        );
+    }
+
+    #[test]
+    fn test_k_shortest_paths() {
+        // Yen's own example
+        let mut graph = crate::Graph::new();
+        graph.add_edge("C", "D", 3);
+        graph.add_edge("C", "E", 2);
+        graph.add_edge("D", "F", 4);
+        graph.add_edge("E", "D", 1);
+        graph.add_edge("E", "F", 2);
+        graph.add_edge("E", "G", 3);
+        graph.add_edge("F", "G", 2);
+        graph.add_edge("F", "H", 1);
+        graph.add_edge("G", "H", 2);
+
+        let paths = k_shortest_paths(&graph, "C", "H", 3).unwrap();
+        assert_eq!(3, paths.len());
+        assert_eq!(&["C", "E", "F", "H"], paths[0].vertices());
+        assert_eq!(&["C", "E", "G", "H"], paths[1].vertices());
+        assert_eq!(
+            vec![5, 7, 8],
+            paths.iter().map(|path| path.cost()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(paths[0].vertices().to_vec()),
+            dijkstra(&graph, "C").unwrap().path_to("H")
+        );
+
+        // Every loopless path from C to H, each one once
+        let all = k_shortest_paths(&graph, "C", "H", 20).unwrap();
+        assert_eq!(7, all.len());
+        assert_eq!(paths[..2], all[..2]);
+        assert!(all.windows(2).all(|pair| pair[0].cost() <= pair[1].cost()));
+        for (i, path) in all.iter().enumerate() {
+            let vertices: HashSet<_> = path.vertices().iter().collect();
+            assert_eq!(path.vertices().len(), vertices.len());
+            assert!(all[i + 1..]
+                .iter()
+                .all(|other| other.vertices() != path.vertices()));
+        }
+    }
+
+    #[test]
+    fn test_k_shortest_paths_cities() {
+        let graph = cities();
+        let paths = k_shortest_paths(&graph, "New York", "Miami", 4).unwrap();
+        assert_eq!(4, paths.len());
+        assert_eq!(
+            shortest_path(&graph, "New York", "Miami").unwrap(),
+            paths[0]
+        );
+        assert_eq!(
+            vec![1290, 2225, 2790, 3125],
+            paths.iter().map(|path| path.cost()).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            &[
+                "New York",
+                "Pittsburgh",
+                "Indianapolis",
+                "Nashville",
+                "Washington, D.C.",
+                "Charlotte",
+                "Miami"
+            ][..],
+            paths[2].vertices()
+        );
+    }
+
+    #[test]
+    fn test_k_shortest_paths_errors() {
+        let mut graph = crate::Graph::new();
+        graph.add_edge(0, 1, 1);
+        graph.add_edge(1, 2, 1);
+        graph.add_vertex(3);
+
+        assert_eq!(Ok(vec![]), k_shortest_paths(&graph, 0, 2, 0));
+        assert_eq!(1, k_shortest_paths(&graph, 0, 2, 5).unwrap().len());
+        assert_eq!(
+            &[1],
+            k_shortest_paths(&graph, 1, 1, 2).unwrap()[0].vertices()
+        );
+        assert_eq!(
+            Err(GraphError::Unreachable(3)),
+            k_shortest_paths(&graph, 0, 3, 2)
+        );
+        assert_eq!(
+            Err(GraphError::MissingVertex(7)),
+            k_shortest_paths(&graph, 0, 7, 2)
+        );
+        graph.add_edge(1, 2, -1);
+        assert_eq!(
+            Err(GraphError::NegativeWeight(1, 2, -1)),
+            k_shortest_paths(&graph, 0, 2, 2)
+        );
+    }
 }
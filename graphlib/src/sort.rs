@@ -1,24 +1,28 @@
-use super::{EdgeTypeTrait, Graph, GraphEdgeTrait, GraphVertexTrait};
+use super::{GraphEdgeTrait, GraphError, GraphStorage, GraphVertexTrait};
 use std::collections::HashMap;
 use std::collections::VecDeque;
 
 /// Performs topological sort using the Kahn's algorithm.
 /// Returns a Vec storing the vertices in a the topological order.
-pub fn topological_sort<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait>(
-    graph: &Graph<V, E, T>,
-) -> Option<Vec<V>> {
+/// Fails with NotADag when the graph has a cycle.
+pub fn topological_sort<V, E, G>(graph: &G) -> Result<Vec<V>, GraphError<V, E>>
+where
+    V: GraphVertexTrait,
+    E: GraphEdgeTrait,
+    G: GraphStorage<Vertex = V, Edge = E>,
+{
     // 1) Preparation:
     //  Build a map of vertices with incoming edges count
     //  Add vertices that have no incoming edges to a queue
     let mut incoming_edges_count: HashMap<V, usize> = HashMap::new();
-    for elem in graph.edges() {
-        incoming_edges_count.entry(elem.0).or_insert(0);
-        *incoming_edges_count.entry(elem.1).or_insert(0) += 1;
+    for elem in graph.iter_edges() {
+        incoming_edges_count.entry(elem.0.clone()).or_insert(0);
+        *incoming_edges_count.entry(elem.1.clone()).or_insert(0) += 1;
     }
     let mut no_incoming_edges: VecDeque<V> = VecDeque::new();
     for (node, count) in &incoming_edges_count {
         if *count == 0 {
-            no_incoming_edges.push_back(*node);
+            no_incoming_edges.push_back(node.clone());
         }
     }
 
@@ -31,33 +35,33 @@ pub fn topological_sort<V: GraphVertexTrait, E: GraphEdgeTrait, T: EdgeTypeTrait
 
     let mut sorted = Vec::new();
     while let Some(edge) = no_incoming_edges.pop_back() {
-        sorted.push(edge);
         incoming_edges_count.remove(&edge);
-        for adjancent in graph.get_adjacent_vertices(edge).unwrap_or(&vec![]) {
-            if let Some(count) = incoming_edges_count.get_mut(&adjancent.0) {
+        for adjancent in graph.neighbours(&edge) {
+            if let Some(count) = incoming_edges_count.get_mut(adjancent.0) {
                 *count -= 1;
                 if *count == 0 {
-                    no_incoming_edges.push_front(adjancent.0);
+                    no_incoming_edges.push_front(adjancent.0.clone());
                 }
             }
         }
+        sorted.push(edge);
     }
 
     // If we have remaining vertices with incoming edges til this point, its a cyclic graph (we have cycles)
     if !incoming_edges_count.is_empty() {
-        return None;
+        return Err(GraphError::NotADag);
     }
 
-    Some(sorted)
+    Ok(sorted)
 }
 
 #[cfg(test)]
 mod test_search {
-    use crate::topological_sort;
+    use crate::{topological_sort, GraphError};
 
     #[test]
     fn test_sort_with_cycle() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_vertex(1);
         graph.add_vertex(2);
         graph.add_vertex(3);
@@ -65,12 +69,12 @@ mod test_search {
         graph.add_edge(2, 3, 0);
         graph.add_edge(3, 1, 0);
         let sort = topological_sort(&graph);
-        assert_eq!(true, sort.is_none());
+        assert_eq!(Err(GraphError::NotADag), sort);
     }
 
     #[test]
     fn test_sort_one_variant() {
-        let mut graph = super::Graph::new();
+        let mut graph = crate::Graph::new();
         graph.add_vertex(1);
         graph.add_vertex(2);
         graph.add_vertex(3);
@@ -91,7 +95,7 @@ mod test_search {
 
     #[test]
     fn test_sort_many_variants() {
-        let mut graph1 = super::Graph::new();
+        let mut graph1 = crate::Graph::new();
         graph1.add_vertex(2);
         graph1.add_vertex(3);
         graph1.add_vertex(5);
@@ -110,6 +114,28 @@ mod test_search {
         graph1.add_edge(11, 9, 0);
         graph1.add_edge(11, 10, 0);
         graph1.add_edge(8, 9, 0);
-        topological_sort(&graph1);
+        assert!(topological_sort(&graph1).is_ok());
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    struct Task {
+        name: String,
+        owner: String,
+    }
+
+    #[test]
+    fn test_sort_struct_vertices() {
+        let task = |name: &str| Task {
+            name: name.to_string(),
+            owner: "ops".to_string(),
+        };
+        let mut graph = crate::Graph::new();
+        graph.add_edge(task("build"), task("test"), 0);
+        graph.add_edge(task("test"), task("deploy"), 0);
+        graph.add_edge(task("build"), task("deploy"), 0);
+
+        let sort = topological_sort(&graph).unwrap();
+        let names: Vec<&str> = sort.iter().map(|task| task.name.as_str()).collect();
+        assert_eq!(vec!["build", "test", "deploy"], names);
     }
 }
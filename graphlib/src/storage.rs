@@ -0,0 +1,67 @@
+use super::{CsrGraph, EdgeTypeTrait, GraphPayloadTrait, GraphVertexTrait};
+
+// Read-only view of a graph shared by every representation, so the algorithms
+// don't depend on how the edges are stored.
+
+/// What the algorithms need from a graph, whatever its storage.
+/// Undirected edges are seen from both ends, as with `Graph::edges`.
+pub trait GraphStorage {
+    type Vertex: GraphVertexTrait;
+    type Edge: GraphPayloadTrait;
+    type EdgeType: EdgeTypeTrait;
+
+    fn is_directed(&self) -> bool {
+        Self::EdgeType::is_directed()
+    }
+
+    fn vertices_count(&self) -> usize;
+
+    fn contains_vertex(&self, vertex: &Self::Vertex) -> bool;
+
+    /// Every vertex, in ascending order
+    fn iter_vertices(&self) -> impl Iterator<Item = &Self::Vertex>;
+
+    /// Edges leaving the vertex as (to, value) pairs, none when it isn't in the graph
+    fn neighbours(
+        &self,
+        vertex: &Self::Vertex,
+    ) -> impl Iterator<Item = (&Self::Vertex, &Self::Edge)>;
+
+    /// Edges arriving at the vertex as (from, value) pairs, none when it isn't in the graph.
+    /// Undirected edges are their own reverse, directed ones are found by scanning every edge
+    /// unless the storage keeps them indexed.
+    fn incoming<'a>(
+        &'a self,
+        vertex: &'a Self::Vertex,
+    ) -> impl Iterator<Item = (&'a Self::Vertex, &'a Self::Edge)> {
+        let directed = self.is_directed();
+        let mirrored = (!directed).then(|| self.neighbours(vertex));
+        let scanned = directed.then(|| {
+            self.iter_edges()
+                .filter(move |(_, to, _)| *to == vertex)
+                .map(|(from, _, value)| (from, value))
+        });
+        mirrored
+            .into_iter()
+            .flatten()
+            .chain(scanned.into_iter().flatten())
+    }
+
+    /// Every edge as (from, to, value), grouped by source vertex in ascending order
+    fn iter_edges(&self) -> impl Iterator<Item = (&Self::Vertex, &Self::Vertex, &Self::Edge)> {
+        self.iter_vertices().flat_map(move |from| {
+            self.neighbours(from)
+                .map(move |(to, value)| (from, to, value))
+        })
+    }
+
+    fn edges_count(&self) -> usize {
+        self.iter_edges().count()
+    }
+
+    /// The storage itself when it is a frozen CsrGraph, so the traversals and Dijkstra
+    /// can walk its dense indices with plain vectors instead of looking vertices up
+    fn as_csr(&self) -> Option<&CsrGraph<Self::Vertex, Self::Edge, Self::EdgeType>> {
+        None
+    }
+}